@@ -0,0 +1,46 @@
+/*
+ * Prints the squares of a dictionary file.
+ *
+ *   cargo build --release -p symmetric-word-triples-ffi
+ *   cc examples/example.c -Iinclude -L../target/release -lsymmetric_word_triples_ffi -o example
+ *   LD_LIBRARY_PATH=../target/release ./example words.txt 3 3
+ */
+#include <stdio.h>
+#include <stdlib.h>
+
+#include "symmetric_word_triples.h"
+
+static int print_square(const char *const *rows, size_t row_count, void *user_data) {
+    size_t *count = user_data;
+    for (size_t i = 0; i < row_count; i++) {
+        printf(i + 1 < row_count ? "%s " : "%s\n", rows[i]);
+    }
+    (*count)++;
+    return 0;
+}
+
+int main(int argc, char **argv) {
+    if (argc != 4) {
+        fprintf(stderr, "Usage: %s <dictionary> <grid size> <chunk size>\n", argv[0]);
+        return 2;
+    }
+
+    SwtPrefixMap *prefix_map = NULL;
+    SwtStatus status = swt_prefix_map_from_file(
+        argv[1], strtoul(argv[2], NULL, 10), strtoul(argv[3], NULL, 10), &prefix_map);
+    if (status != SWT_STATUS_OK) {
+        fprintf(stderr, "Error: %s\n", swt_last_error_message());
+        return 1;
+    }
+
+    size_t count = 0;
+    status = swt_full_search(prefix_map, print_square, &count);
+    if (status != SWT_STATUS_OK) {
+        fprintf(stderr, "Error: %s\n", swt_last_error_message());
+    } else {
+        fprintf(stderr, "%zu squares\n", count);
+    }
+
+    swt_prefix_map_free(prefix_map);
+    return status == SWT_STATUS_OK ? 0 : 1;
+}
@@ -0,0 +1,135 @@
+#ifndef SYMMETRIC_WORD_TRIPLES_H
+#define SYMMETRIC_WORD_TRIPLES_H
+
+/* Generated by cbindgen from symmetric-word-triples-ffi. Do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * Result of every call.
+ */
+typedef enum SwtStatus {
+  SWT_STATUS_OK = 0,
+  /**
+   * A required pointer argument was null.
+   */
+  SWT_STATUS_NULL_POINTER = 1,
+  /**
+   * An argument was out of range or not valid UTF-8.
+   */
+  SWT_STATUS_INVALID_ARGUMENT = 2,
+  /**
+   * The dictionary file could not be read.
+   */
+  SWT_STATUS_IO_ERROR = 3,
+  /**
+   * The word is not in the dictionary or has the wrong length.
+   */
+  SWT_STATUS_UNKNOWN_WORD = 4,
+  /**
+   * The library panicked. The handle should not be used anymore.
+   */
+  SWT_STATUS_PANIC = 5,
+} SwtStatus;
+
+/**
+ * A dictionary loaded into a prefix map for one grid and chunk size.
+ */
+typedef struct SwtPrefixMap SwtPrefixMap;
+
+/**
+ * Called once per solution with the row words of the square.
+ * The strings are only valid during the call. Return 0 to continue, anything else to stop.
+ */
+typedef int (*SwtSolutionCallback)(const char *const *rows, size_t row_count, void *user_data);
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Loads a dictionary file and builds a prefix map for the grid and chunk size.
+ * On success `*out` holds a handle that has to be freed with `swt_prefix_map_free`.
+ *
+ * # Safety
+ * `path` has to be a null-terminated string and `out` a valid pointer.
+ */
+enum SwtStatus swt_prefix_map_from_file(const char *path,
+                                        size_t grid_size,
+                                        size_t chunk_size,
+                                        struct SwtPrefixMap **out);
+
+/**
+ * Builds a prefix map from `word_count` words for the grid and chunk size.
+ * On success `*out` holds a handle that has to be freed with `swt_prefix_map_free`.
+ *
+ * # Safety
+ * `words` has to point to `word_count` null-terminated strings and `out` has to be valid.
+ */
+enum SwtStatus swt_prefix_map_from_words(const char *const *words,
+                                         size_t word_count,
+                                         size_t grid_size,
+                                         size_t chunk_size,
+                                         struct SwtPrefixMap **out);
+
+/**
+ * Frees a handle. Null is ignored.
+ *
+ * # Safety
+ * `handle` has to come from one of the constructors and must not be used afterwards.
+ */
+void swt_prefix_map_free(struct SwtPrefixMap *handle);
+
+/**
+ * Number of dictionary words that fit the grid and chunk size.
+ *
+ * # Safety
+ * `handle` has to be a live handle and `out` a valid pointer.
+ */
+enum SwtStatus swt_prefix_map_word_count(const struct SwtPrefixMap *handle, size_t *out);
+
+/**
+ * Calls `callback` for every square with `word` in the first row.
+ *
+ * # Safety
+ * `handle` has to be a live handle and `word` a null-terminated string.
+ */
+enum SwtStatus swt_seed_search(const struct SwtPrefixMap *handle,
+                               const char *word,
+                               SwtSolutionCallback callback,
+                               void *user_data);
+
+/**
+ * Calls `callback` for every square of the dictionary, ordered by the first row.
+ * The callback is always called on the calling thread.
+ *
+ * # Safety
+ * `handle` has to be a live handle.
+ */
+enum SwtStatus swt_full_search(const struct SwtPrefixMap *handle,
+                               SwtSolutionCallback callback,
+                               void *user_data);
+
+/**
+ * Counts the squares with `word` in the first row.
+ *
+ * # Safety
+ * `handle` has to be a live handle, `word` a null-terminated string and `out` valid.
+ */
+enum SwtStatus swt_count(const struct SwtPrefixMap *handle, const char *word, size_t *out);
+
+/**
+ * The message of the last failed call on this thread, or null if there was none.
+ * The string stays valid until the next failed call on the same thread.
+ */
+const char *swt_last_error_message(void);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif // __cplusplus
+
+#endif /* SYMMETRIC_WORD_TRIPLES_H */
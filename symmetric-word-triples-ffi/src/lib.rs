@@ -0,0 +1,328 @@
+//! C interface to the symmetric word square search.
+//!
+//! Every function returns a [`SwtStatus`]. On failure the message of the last error on the
+//! calling thread is available from [`swt_last_error_message`]. Panics never cross the boundary,
+//! they are reported as [`SwtStatus::Panic`].
+
+use std::cell::RefCell;
+use std::ffi::{c_char, c_int, c_void, CStr, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::Path;
+use std::ptr;
+use std::sync::Arc;
+use symmetric_word_triples::parser::{
+    self,
+    memo::MemoStrategy,
+    token::TokenWord,
+    wordfilter::{PrefixMap, WordDict, WordFilter},
+};
+
+/// Result of every call.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SwtStatus {
+    Ok = 0,
+    /// A required pointer argument was null.
+    NullPointer = 1,
+    /// An argument was out of range or not valid UTF-8.
+    InvalidArgument = 2,
+    /// The dictionary file could not be read.
+    IoError = 3,
+    /// The word is not in the dictionary or has the wrong length.
+    UnknownWord = 4,
+    /// The library panicked. The handle should not be used anymore.
+    Panic = 5,
+}
+
+/// A dictionary loaded into a prefix map for one grid and chunk size.
+pub struct SwtPrefixMap {
+    prefix_map: Arc<PrefixMap>,
+}
+
+/// Called once per solution with the row words of the square.
+/// The strings are only valid during the call. Return 0 to continue, anything else to stop.
+pub type SwtSolutionCallback = Option<
+    unsafe extern "C" fn(
+        rows: *const *const c_char,
+        row_count: usize,
+        user_data: *mut c_void,
+    ) -> c_int,
+>;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+type FfiResult<T> = Result<T, (SwtStatus, String)>;
+
+fn set_last_error(message: String) {
+    let message = CString::new(message.replace('\0', " ")).unwrap_or_default();
+    LAST_ERROR.with(|last_error| *last_error.borrow_mut() = Some(message));
+}
+
+/// Runs `f`, turning errors and panics into a status code and the thread's last error.
+fn ffi_call<F: FnOnce() -> FfiResult<()>>(f: F) -> SwtStatus {
+    match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(())) => SwtStatus::Ok,
+        Ok(Err((status, message))) => {
+            set_last_error(message);
+            status
+        }
+        Err(panic) => {
+            let message = panic
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| panic.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "Unknown panic.".to_string());
+            set_last_error(message);
+            SwtStatus::Panic
+        }
+    }
+}
+
+unsafe fn str_arg<'a>(s: *const c_char, name: &str) -> FfiResult<&'a str> {
+    if s.is_null() {
+        return Err((SwtStatus::NullPointer, format!("{name} is null.")));
+    }
+    CStr::from_ptr(s).to_str().map_err(|_| {
+        (
+            SwtStatus::InvalidArgument,
+            format!("{name} is not valid UTF-8."),
+        )
+    })
+}
+
+unsafe fn handle_arg<'a>(handle: *const SwtPrefixMap) -> FfiResult<&'a SwtPrefixMap> {
+    handle.as_ref().ok_or_else(|| {
+        (
+            SwtStatus::NullPointer,
+            "The prefix map handle is null.".to_string(),
+        )
+    })
+}
+
+fn build(
+    mut word_dictionary: WordDict,
+    grid_size: usize,
+    chunk_size: usize,
+) -> FfiResult<*mut SwtPrefixMap> {
+    if grid_size == 0 || chunk_size == 0 {
+        return Err((
+            SwtStatus::InvalidArgument,
+            "Grid size and chunk size have to be at least 1.".to_string(),
+        ));
+    }
+    parser::len_filter(&mut word_dictionary, grid_size * chunk_size);
+    let prefix_map = PrefixMap::new(
+        &word_dictionary,
+        grid_size,
+        chunk_size,
+        MemoStrategy::for_grid_size(grid_size),
+    );
+    Ok(Box::into_raw(Box::new(SwtPrefixMap {
+        prefix_map: Arc::new(prefix_map),
+    })))
+}
+
+fn known_word(prefix_map: &PrefixMap, word: &str) -> FfiResult<TokenWord> {
+    prefix_map
+        .try_tokenize_word(word)
+        .filter(|tkn_word| prefix_map.contains(tkn_word))
+        .ok_or_else(|| {
+            (
+                SwtStatus::UnknownWord,
+                format!("\"{word}\" is not a word of the dictionary with the grid size."),
+            )
+        })
+}
+
+/// Calls the callback for every solution of `seeds` in order, until it asks to stop.
+fn run_callback<I>(
+    prefix_map: &Arc<PrefixMap>,
+    seeds: I,
+    callback: SwtSolutionCallback,
+    user_data: *mut c_void,
+) -> FfiResult<()>
+where
+    I: IntoIterator<Item = TokenWord>,
+{
+    let callback = callback.ok_or_else(|| {
+        (
+            SwtStatus::NullPointer,
+            "The solution callback is null.".to_string(),
+        )
+    })?;
+    for seed in seeds {
+        for solution in prefix_map
+            .symmetric_words_iter(seed)
+            .map_err(|error| (SwtStatus::InvalidArgument, error))?
+        {
+            let rows = prefix_map
+                .stringify_token_rows(&solution)
+                .into_iter()
+                .map(|row| CString::new(row).unwrap_or_default())
+                .collect::<Vec<_>>();
+            let row_ptrs = rows.iter().map(|row| row.as_ptr()).collect::<Vec<_>>();
+            if unsafe { callback(row_ptrs.as_ptr(), row_ptrs.len(), user_data) } != 0 {
+                return Ok(());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Loads a dictionary file and builds a prefix map for the grid and chunk size.
+/// On success `*out` holds a handle that has to be freed with `swt_prefix_map_free`.
+///
+/// # Safety
+/// `path` has to be a null-terminated string and `out` a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn swt_prefix_map_from_file(
+    path: *const c_char,
+    grid_size: usize,
+    chunk_size: usize,
+    out: *mut *mut SwtPrefixMap,
+) -> SwtStatus {
+    ffi_call(|| {
+        let path = str_arg(path, "path")?;
+        let out = out
+            .as_mut()
+            .ok_or_else(|| (SwtStatus::NullPointer, "out is null.".to_string()))?;
+        let mut word_dictionary = vec![];
+        parser::file_vec(Path::new(path), &mut word_dictionary)
+            .map_err(|error| (SwtStatus::IoError, format!("{path}: {error}")))?;
+        *out = build(word_dictionary, grid_size, chunk_size)?;
+        Ok(())
+    })
+}
+
+/// Builds a prefix map from `word_count` words for the grid and chunk size.
+/// On success `*out` holds a handle that has to be freed with `swt_prefix_map_free`.
+///
+/// # Safety
+/// `words` has to point to `word_count` null-terminated strings and `out` has to be valid.
+#[no_mangle]
+pub unsafe extern "C" fn swt_prefix_map_from_words(
+    words: *const *const c_char,
+    word_count: usize,
+    grid_size: usize,
+    chunk_size: usize,
+    out: *mut *mut SwtPrefixMap,
+) -> SwtStatus {
+    ffi_call(|| {
+        let out = out
+            .as_mut()
+            .ok_or_else(|| (SwtStatus::NullPointer, "out is null.".to_string()))?;
+        if words.is_null() && word_count > 0 {
+            return Err((SwtStatus::NullPointer, "words is null.".to_string()));
+        }
+        let word_dictionary = (0..word_count)
+            .map(|i| str_arg(*words.add(i), "word").map(str::to_string))
+            .collect::<FfiResult<WordDict>>()?;
+        *out = build(word_dictionary, grid_size, chunk_size)?;
+        Ok(())
+    })
+}
+
+/// Frees a handle. Null is ignored.
+///
+/// # Safety
+/// `handle` has to come from one of the constructors and must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn swt_prefix_map_free(handle: *mut SwtPrefixMap) {
+    if !handle.is_null() {
+        drop(Box::from_raw(handle));
+    }
+}
+
+/// Number of dictionary words that fit the grid and chunk size.
+///
+/// # Safety
+/// `handle` has to be a live handle and `out` a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn swt_prefix_map_word_count(
+    handle: *const SwtPrefixMap,
+    out: *mut usize,
+) -> SwtStatus {
+    ffi_call(|| {
+        let handle = handle_arg(handle)?;
+        let out = out
+            .as_mut()
+            .ok_or_else(|| (SwtStatus::NullPointer, "out is null.".to_string()))?;
+        *out = handle.prefix_map.get_prefix_words(&TokenWord::new()).len();
+        Ok(())
+    })
+}
+
+/// Calls `callback` for every square with `word` in the first row.
+///
+/// # Safety
+/// `handle` has to be a live handle and `word` a null-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn swt_seed_search(
+    handle: *const SwtPrefixMap,
+    word: *const c_char,
+    callback: SwtSolutionCallback,
+    user_data: *mut c_void,
+) -> SwtStatus {
+    ffi_call(|| {
+        let handle = handle_arg(handle)?;
+        let word = known_word(&handle.prefix_map, str_arg(word, "word")?)?;
+        run_callback(&handle.prefix_map, [word], callback, user_data)
+    })
+}
+
+/// Calls `callback` for every square of the dictionary, ordered by the first row.
+/// The callback is always called on the calling thread.
+///
+/// # Safety
+/// `handle` has to be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn swt_full_search(
+    handle: *const SwtPrefixMap,
+    callback: SwtSolutionCallback,
+    user_data: *mut c_void,
+) -> SwtStatus {
+    ffi_call(|| {
+        let handle = handle_arg(handle)?;
+        let seeds = handle.prefix_map.get_prefix_words(&TokenWord::new());
+        let seeds = seeds.iter().map(|seed| (**seed).clone());
+        run_callback(&handle.prefix_map, seeds, callback, user_data)
+    })
+}
+
+/// Counts the squares with `word` in the first row.
+///
+/// # Safety
+/// `handle` has to be a live handle, `word` a null-terminated string and `out` valid.
+#[no_mangle]
+pub unsafe extern "C" fn swt_count(
+    handle: *const SwtPrefixMap,
+    word: *const c_char,
+    out: *mut usize,
+) -> SwtStatus {
+    ffi_call(|| {
+        let handle = handle_arg(handle)?;
+        let out = out
+            .as_mut()
+            .ok_or_else(|| (SwtStatus::NullPointer, "out is null.".to_string()))?;
+        let word = known_word(&handle.prefix_map, str_arg(word, "word")?)?;
+        *out = handle
+            .prefix_map
+            .count_symmetric_words_single(word)
+            .map_err(|error| (SwtStatus::InvalidArgument, error))?;
+        Ok(())
+    })
+}
+
+/// The message of the last failed call on this thread, or null if there was none.
+/// The string stays valid until the next failed call on the same thread.
+#[no_mangle]
+pub extern "C" fn swt_last_error_message() -> *const c_char {
+    LAST_ERROR.with(|last_error| {
+        last_error
+            .borrow()
+            .as_ref()
+            .map_or(ptr::null(), |message| message.as_ptr())
+    })
+}
@@ -0,0 +1,108 @@
+use std::ffi::{c_char, c_int, c_void, CStr, CString};
+use std::ptr;
+use symmetric_word_triples_ffi::*;
+
+fn prefix_map() -> *mut SwtPrefixMap {
+    let words =
+        ["complaint", "placement", "intention", "ab"].map(|word| CString::new(word).unwrap());
+    let word_ptrs = words.iter().map(|word| word.as_ptr()).collect::<Vec<_>>();
+    let mut handle = ptr::null_mut();
+    let status = unsafe {
+        swt_prefix_map_from_words(word_ptrs.as_ptr(), word_ptrs.len(), 3, 3, &mut handle)
+    };
+    assert_eq!(status, SwtStatus::Ok);
+    handle
+}
+
+unsafe extern "C" fn collect(
+    rows: *const *const c_char,
+    row_count: usize,
+    user_data: *mut c_void,
+) -> c_int {
+    let solutions = &mut *(user_data as *mut Vec<String>);
+    let rows = (0..row_count)
+        .map(|i| CStr::from_ptr(*rows.add(i)).to_str().unwrap())
+        .collect::<Vec<_>>();
+    solutions.push(rows.join(" "));
+    0
+}
+
+unsafe extern "C" fn stop(_: *const *const c_char, _: usize, user_data: *mut c_void) -> c_int {
+    *(user_data as *mut usize) += 1;
+    1
+}
+
+#[test]
+fn seed_and_full_search() {
+    let handle = prefix_map();
+    let mut solutions: Vec<String> = vec![];
+    let word = CString::new("complaint").unwrap();
+    let user_data = &mut solutions as *mut Vec<String> as *mut c_void;
+    unsafe {
+        assert_eq!(
+            swt_seed_search(handle, word.as_ptr(), Some(collect), user_data),
+            SwtStatus::Ok
+        );
+        assert_eq!(solutions, ["complaint placement intention"]);
+
+        solutions.clear();
+        assert_eq!(
+            swt_full_search(handle, Some(collect), user_data),
+            SwtStatus::Ok
+        );
+        assert_eq!(solutions.len(), 1);
+
+        let mut count = 0;
+        assert_eq!(swt_count(handle, word.as_ptr(), &mut count), SwtStatus::Ok);
+        assert_eq!(count, 1);
+
+        let mut calls = 0usize;
+        let user_data = &mut calls as *mut usize as *mut c_void;
+        assert_eq!(
+            swt_full_search(handle, Some(stop), user_data),
+            SwtStatus::Ok
+        );
+        assert_eq!(calls, 1);
+
+        swt_prefix_map_free(handle);
+    }
+}
+
+#[test]
+fn errors_set_last_message() {
+    let handle = prefix_map();
+    let word = CString::new("ab").unwrap();
+    let missing = CString::new("/does/not/exist.txt").unwrap();
+    let mut count = 0;
+    let mut other = ptr::null_mut();
+    unsafe {
+        assert_eq!(
+            swt_count(handle, word.as_ptr(), &mut count),
+            SwtStatus::UnknownWord
+        );
+        let message = CStr::from_ptr(swt_last_error_message()).to_str().unwrap();
+        assert!(message.contains("\"ab\""));
+
+        assert_eq!(
+            swt_count(ptr::null(), word.as_ptr(), &mut count),
+            SwtStatus::NullPointer
+        );
+        assert_eq!(
+            swt_prefix_map_from_file(missing.as_ptr(), 3, 3, &mut other),
+            SwtStatus::IoError
+        );
+        assert!(other.is_null());
+
+        swt_prefix_map_free(handle);
+    }
+}
+
+#[test]
+fn committed_header_is_up_to_date() {
+    let generated = include_str!(concat!(env!("OUT_DIR"), "/symmetric_word_triples.h"));
+    let committed = include_str!("../include/symmetric_word_triples.h");
+    assert!(
+        generated == committed,
+        "include/symmetric_word_triples.h is out of date, regenerate it with cbindgen."
+    );
+}
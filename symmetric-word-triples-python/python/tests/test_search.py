@@ -0,0 +1,52 @@
+import threading
+
+import symmetric_word_triples as swt
+
+WORDS = ["complaint", "placement", "intention", "apple", "ab", "ba", "bb"]
+
+
+def test_seed_search():
+    prefix_map = swt.PrefixMap(WORDS, 3, 3)
+    assert prefix_map.seed_search("complaint") == [("complaint", "placement", "intention")]
+    assert prefix_map.count("complaint") == 1
+    assert prefix_map.prefix_words("com") == ["complaint"]
+
+
+def test_limit_and_sample():
+    prefix_map = swt.PrefixMap(WORDS, 2, 1)
+    assert len(prefix_map) == 3
+    assert prefix_map.seed_search("ab", limit=1) == [("ab", "ba")]
+    sample = prefix_map.seed_search("ab", limit=1, seed=7)
+    assert sample == prefix_map.seed_search("ab", limit=1, seed=7)
+    assert len(prefix_map.search_all()) == 5
+
+
+def test_unknown_word():
+    prefix_map = swt.PrefixMap(WORDS, 3, 3)
+    try:
+        prefix_map.seed_search("complains")
+    except ValueError:
+        pass
+    else:
+        raise AssertionError("expected ValueError")
+
+
+def test_search_file(tmp_path):
+    path = tmp_path / "words.txt"
+    path.write_text("\n".join(WORDS))
+    assert swt.load_dictionary(str(path)) == WORDS
+    assert swt.search_file(str(path), 3, 3) == [("complaint", "placement", "intention")]
+
+
+def test_releases_gil():
+    prefix_map = swt.PrefixMap(WORDS, 2, 1)
+    results = []
+    threads = [
+        threading.Thread(target=lambda: results.append(prefix_map.search_all()))
+        for _ in range(4)
+    ]
+    for thread in threads:
+        thread.start()
+    for thread in threads:
+        thread.join()
+    assert len(results) == 4
@@ -0,0 +1,206 @@
+// The code generated by `#[pyfunction]` and `#[pymethods]` trips this lint on every `PyResult`.
+#![allow(clippy::useless_conversion)]
+
+use pyo3::exceptions::{PyIOError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyTuple;
+use std::path::PathBuf;
+use std::sync::Arc;
+use symmetric_word_triples::parser::{
+    self,
+    matrix::TokenMatrix,
+    memo::{MemoStrategy, DEFAULT_MEMO_CAPACITY},
+    token::TokenWord,
+    wordfilter::{self, SearchMode, WordDict, WordFilter},
+};
+
+/// Reads a dictionary file with one word per line.
+#[pyfunction]
+fn load_dictionary(py: Python<'_>, path: PathBuf) -> PyResult<Vec<String>> {
+    py.allow_threads(|| {
+        let mut word_dictionary = vec![];
+        parser::file_vec(&path, &mut word_dictionary)?;
+        Ok(word_dictionary)
+    })
+    .map_err(|error: std::io::Error| PyIOError::new_err(error.to_string()))
+}
+
+/// Finds all symmetric squares of a dictionary file. Every square is a tuple of its rows.
+#[pyfunction]
+fn search_file(
+    py: Python<'_>,
+    path: PathBuf,
+    grid_size: usize,
+    chunk_size: usize,
+) -> PyResult<Vec<Py<PyTuple>>> {
+    let word_dictionary = load_dictionary(py, path)?;
+    let prefix_map = PrefixMap::new(py, word_dictionary, grid_size, chunk_size, None)?;
+    Ok(prefix_map.search_all(py, None, None))
+}
+
+/// The prefix index of a dictionary for one grid and chunk size.
+#[pyclass]
+struct PrefixMap {
+    inner: wordfilter::PrefixMap,
+}
+
+#[pymethods]
+impl PrefixMap {
+    /// Builds the index from a list of words. Words of the wrong length are ignored.
+    /// `use_table` caches prefix lookups in a bounded table. By default the table is used
+    /// for grids of more than two words, and turned off if it rarely helps.
+    #[new]
+    #[pyo3(signature = (words, grid_size, chunk_size, use_table = None))]
+    fn new(
+        py: Python<'_>,
+        words: WordDict,
+        grid_size: usize,
+        chunk_size: usize,
+        use_table: Option<bool>,
+    ) -> PyResult<PrefixMap> {
+        if grid_size == 0 || chunk_size == 0 {
+            return Err(PyValueError::new_err(
+                "grid_size and chunk_size have to be at least 1",
+            ));
+        }
+        let inner = py.allow_threads(|| {
+            let mut word_dictionary = words;
+            parser::len_filter(&mut word_dictionary, grid_size * chunk_size);
+            wordfilter::PrefixMap::new(
+                &word_dictionary,
+                grid_size,
+                chunk_size,
+                match use_table {
+                    Some(true) => MemoStrategy::Bounded {
+                        capacity: DEFAULT_MEMO_CAPACITY,
+                    },
+                    Some(false) => MemoStrategy::Off,
+                    None => MemoStrategy::for_grid_size(grid_size),
+                },
+            )
+        });
+        Ok(PrefixMap { inner })
+    }
+
+    #[getter]
+    fn grid_size(&self) -> usize {
+        self.inner.grid_size()
+    }
+
+    #[getter]
+    fn chunk_size(&self) -> usize {
+        self.inner.chunk_size()
+    }
+
+    /// Finds the squares with `word` in the first row.
+    /// `limit` stops after that many squares, `seed` returns a reproducible random sample.
+    #[pyo3(signature = (word, limit = None, seed = None))]
+    fn seed_search(
+        &self,
+        py: Python<'_>,
+        word: &str,
+        limit: Option<usize>,
+        seed: Option<u64>,
+    ) -> PyResult<Vec<Py<PyTuple>>> {
+        let word = self.known_word(word)?;
+        let mode = search_mode(limit, seed);
+        let rows = py
+            .allow_threads(|| {
+                let solution_set = self.inner.symmetric_words_single_with(word, mode)?;
+                Ok::<_, String>(self.rows(&solution_set))
+            })
+            .map_err(PyValueError::new_err)?;
+        Ok(to_tuples(py, rows))
+    }
+
+    /// Finds the squares of every word of the dictionary, in parallel.
+    /// `limit` and `seed` apply to every first-row word separately.
+    #[pyo3(signature = (limit = None, seed = None))]
+    fn search_all(
+        &self,
+        py: Python<'_>,
+        limit: Option<usize>,
+        seed: Option<u64>,
+    ) -> Vec<Py<PyTuple>> {
+        let mode = search_mode(limit, seed);
+        let rows = py.allow_threads(|| self.rows(&self.inner.symmetric_words_all(mode)));
+        to_tuples(py, rows)
+    }
+
+    /// Counts the squares with `word` in the first row.
+    fn count(&self, py: Python<'_>, word: &str) -> PyResult<usize> {
+        let word = self.known_word(word)?;
+        py.allow_threads(|| self.inner.count_symmetric_words_single(word))
+            .map_err(PyValueError::new_err)
+    }
+
+    /// Returns the words starting with `prefix`, which has to consist of whole chunks.
+    #[pyo3(signature = (prefix = ""))]
+    fn prefix_words(&self, prefix: &str) -> PyResult<Vec<String>> {
+        if !prefix
+            .chars()
+            .count()
+            .is_multiple_of(self.inner.chunk_size())
+        {
+            return Err(PyValueError::new_err(format!(
+                "the prefix has to consist of whole chunks of size {}",
+                self.inner.chunk_size()
+            )));
+        }
+        let words = match self.inner.try_tokenize_word(prefix) {
+            Some(prefix) => self.inner.get_prefix_words(&prefix),
+            None => vec![],
+        };
+        Ok(words
+            .iter()
+            .map(|word| self.inner.stringify_token_word(word))
+            .collect())
+    }
+
+    fn __len__(&self) -> usize {
+        self.inner.get_prefix_words(&TokenWord::new()).len()
+    }
+}
+
+impl PrefixMap {
+    fn known_word(&self, word: &str) -> PyResult<TokenWord> {
+        self.inner
+            .try_tokenize_word(word)
+            .filter(|tkn_word| self.inner.contains(tkn_word))
+            .ok_or_else(|| PyValueError::new_err(format!("{word:?} is not in the dictionary")))
+    }
+
+    fn rows(&self, solution_set: &[Arc<TokenMatrix>]) -> Vec<Vec<String>> {
+        solution_set
+            .iter()
+            .map(|solution| self.inner.stringify_token_rows(solution))
+            .collect()
+    }
+}
+
+fn to_tuples(py: Python<'_>, rows: Vec<Vec<String>>) -> Vec<Py<PyTuple>> {
+    rows.into_iter()
+        .map(|row| PyTuple::new_bound(py, row).unbind())
+        .collect()
+}
+
+fn search_mode(limit: Option<usize>, seed: Option<u64>) -> SearchMode {
+    match (limit, seed) {
+        (None, None) => SearchMode::All,
+        (Some(limit), None) => SearchMode::First { limit },
+        (limit, Some(seed)) => SearchMode::Sample {
+            limit: limit.unwrap_or(usize::MAX),
+            seed,
+            stream: 0,
+        },
+    }
+}
+
+#[pymodule]
+#[pyo3(name = "symmetric_word_triples")]
+fn symmetric_word_triples_python(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_function(wrap_pyfunction!(load_dictionary, module)?)?;
+    module.add_function(wrap_pyfunction!(search_file, module)?)?;
+    module.add_class::<PrefixMap>()?;
+    Ok(())
+}
@@ -0,0 +1,125 @@
+use js_sys::Array;
+use std::sync::Arc;
+use symmetric_word_triples::parser::{
+    self,
+    memo::MemoStrategy,
+    token::TokenWord,
+    wordfilter::{PrefixMap, SymmetricWordsIter, WordDict},
+};
+use wasm_bindgen::prelude::*;
+
+/// Finds symmetric squares of a word list in the browser.
+/// The search runs in steps, so the page can show solutions while it is still searching.
+#[wasm_bindgen]
+pub struct SquareFinder {
+    prefix_map: Arc<PrefixMap>,
+    seeds: Vec<Arc<TokenWord>>,
+    next_seed: usize,
+    current: Option<SymmetricWordsIter>,
+}
+
+#[wasm_bindgen]
+impl SquareFinder {
+    /// Takes an array of words. Whitespace around the words and empty lines are ignored.
+    #[wasm_bindgen(constructor)]
+    pub fn new(words: Array, grid_size: usize, chunk_size: usize) -> Result<SquareFinder, JsError> {
+        let words = words.iter().filter_map(|word| word.as_string()).collect();
+        SquareFinder::from_words(words, grid_size, chunk_size).map_err(|error| JsError::new(&error))
+    }
+
+    /// Only searches the squares with `word` in the first row from now on.
+    #[wasm_bindgen(js_name = seedWord)]
+    pub fn seed_word(&mut self, word: &str) -> Result<(), JsError> {
+        self.restrict_to(word).map_err(|error| JsError::new(&error))
+    }
+
+    /// Returns up to `max` more solutions, each an array of its row words.
+    /// Returns an empty array once the search is done.
+    #[wasm_bindgen(js_name = nextSolutions)]
+    pub fn next_solutions(&mut self, max: usize) -> Array {
+        self.next_rows(max)
+            .into_iter()
+            .map(|rows| rows.into_iter().map(JsValue::from).collect::<Array>())
+            .collect()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn done(&self) -> bool {
+        self.current.is_none() && self.next_seed >= self.seeds.len()
+    }
+
+    /// Number of words that fit the grid and chunk size.
+    #[wasm_bindgen(getter, js_name = wordCount)]
+    pub fn word_count(&self) -> usize {
+        self.seeds.len()
+    }
+}
+
+impl SquareFinder {
+    pub fn from_words(
+        words: WordDict,
+        grid_size: usize,
+        chunk_size: usize,
+    ) -> Result<SquareFinder, String> {
+        if grid_size == 0 || chunk_size == 0 {
+            return Err("Grid size and chunk size have to be at least 1.".to_string());
+        }
+        let mut word_dictionary = words
+            .iter()
+            .map(|word| word.trim().to_string())
+            .filter(|word| !word.is_empty())
+            .collect();
+        parser::len_filter(&mut word_dictionary, grid_size * chunk_size);
+        let prefix_map = Arc::new(PrefixMap::new(
+            &word_dictionary,
+            grid_size,
+            chunk_size,
+            MemoStrategy::for_grid_size(grid_size),
+        ));
+        let seeds = prefix_map.get_prefix_words(&TokenWord::new());
+        Ok(SquareFinder {
+            prefix_map,
+            seeds,
+            next_seed: 0,
+            current: None,
+        })
+    }
+
+    pub fn restrict_to(&mut self, word: &str) -> Result<(), String> {
+        let word = self
+            .prefix_map
+            .try_tokenize_word(word)
+            .filter(|tkn_word| self.prefix_map.contains(tkn_word))
+            .ok_or_else(|| {
+                format!("\"{word}\" is not a word of the list with the right length.")
+            })?;
+        self.seeds = vec![Arc::new(word)];
+        self.next_seed = 0;
+        self.current = None;
+        Ok(())
+    }
+
+    /// Returns up to `max` more solutions as row words.
+    pub fn next_rows(&mut self, max: usize) -> Vec<Vec<String>> {
+        let mut solutions = vec![];
+        while solutions.len() < max {
+            let current = match &mut self.current {
+                Some(current) => current,
+                None => {
+                    let Some(seed) = self.seeds.get(self.next_seed) else {
+                        break;
+                    };
+                    self.next_seed += 1;
+                    let iter = self.prefix_map.symmetric_words_iter((**seed).clone());
+                    self.current
+                        .insert(iter.expect("Seeds have the grid size."))
+                }
+            };
+            match current.next() {
+                Some(solution) => solutions.push(self.prefix_map.stringify_token_rows(&solution)),
+                None => self.current = None,
+            }
+        }
+        solutions
+    }
+}
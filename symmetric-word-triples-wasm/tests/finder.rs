@@ -0,0 +1,45 @@
+use symmetric_word_triples_wasm::SquareFinder;
+
+fn words(words: &[&str]) -> Vec<String> {
+    words.iter().map(|word| word.to_string()).collect()
+}
+
+#[test]
+fn solutions_in_batches() {
+    let mut finder =
+        SquareFinder::from_words(words(&["ab", " ba ", "bb", "", "abc"]), 2, 1).unwrap();
+    assert_eq!(finder.word_count(), 3);
+
+    let mut solutions = vec![];
+    loop {
+        let batch = finder.next_rows(2);
+        assert!(batch.len() <= 2);
+        if batch.is_empty() {
+            break;
+        }
+        solutions.extend(batch);
+    }
+    assert!(finder.done());
+    assert_eq!(
+        solutions,
+        vec![
+            words(&["ab", "ba"]),
+            words(&["ab", "bb"]),
+            words(&["ba", "ab"]),
+            words(&["bb", "ba"]),
+            words(&["bb", "bb"]),
+        ]
+    );
+}
+
+#[test]
+fn restrict_to_word() {
+    let mut finder =
+        SquareFinder::from_words(words(&["complaint", "placement", "intention"]), 3, 3).unwrap();
+    finder.restrict_to("complaint").unwrap();
+    assert_eq!(
+        finder.next_rows(10),
+        vec![words(&["complaint", "placement", "intention"])]
+    );
+    assert!(finder.restrict_to("placemint").is_err());
+}
@@ -0,0 +1,136 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use symmetric_word_triples::parser::{
+    self, ascii,
+    memo::{MemoStrategy, DEFAULT_MEMO_CAPACITY},
+    token::{TokenWord, Tokens},
+    wordfilter::{PrefixMap, SearchMode, WordDict},
+};
+use symmetric_word_triples::synthetic::SyntheticDictionary;
+
+/// The grid and chunk sizes every stage is measured with.
+const SIZES: [(usize, usize); 4] = [(2, 2), (3, 2), (3, 3), (4, 2)];
+
+fn dictionary(grid_size: usize, chunk_size: usize) -> WordDict {
+    let mut dict = SyntheticDictionary {
+        alphabet: "abcdefgh".chars().collect(),
+        word_count: 20_000,
+        grid_size,
+        chunk_size,
+        chunk_overlap: 0.6,
+        seed: 42,
+    }
+    .generate();
+    parser::len_filter(&mut dict, grid_size * chunk_size);
+    dict
+}
+
+fn size_id(grid_size: usize, chunk_size: usize) -> String {
+    format!("grid{grid_size}_chunk{chunk_size}")
+}
+
+pub fn benchmark_tokenize(c: &mut Criterion) {
+    let mut group = c.benchmark_group("chunkify and tokenize");
+    for (grid_size, chunk_size) in SIZES {
+        let dict = dictionary(grid_size, chunk_size);
+        group.bench_with_input(
+            BenchmarkId::new("general", size_id(grid_size, chunk_size)),
+            &dict,
+            |b, dict| {
+                b.iter(|| {
+                    let mut tokens = Tokens::new();
+                    parser::chunkify_dict(dict, grid_size, chunk_size)
+                        .into_iter()
+                        .map(|chunky| {
+                            chunky
+                                .into_iter()
+                                .map(|chunk| tokens.insert(chunk))
+                                .collect::<TokenWord>()
+                        })
+                        .collect::<Vec<_>>()
+                })
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("ascii", size_id(grid_size, chunk_size)),
+            &dict,
+            |b, dict| {
+                b.iter(|| {
+                    ascii::tokenize_dict(dict, grid_size, chunk_size, &mut Tokens::new()).unwrap()
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+pub fn benchmark_prefix_map_new(c: &mut Criterion) {
+    let mut group = c.benchmark_group("PrefixMap::new");
+    for (grid_size, chunk_size) in SIZES {
+        let dict = dictionary(grid_size, chunk_size);
+        group.bench_with_input(
+            BenchmarkId::from_parameter(size_id(grid_size, chunk_size)),
+            &dict,
+            |b, dict| b.iter(|| PrefixMap::new(dict, grid_size, chunk_size, MemoStrategy::Off)),
+        );
+    }
+    group.finish();
+}
+
+pub fn benchmark_get_prefix_words(c: &mut Criterion) {
+    let mut group = c.benchmark_group("get_prefix_words");
+    for (grid_size, chunk_size) in SIZES {
+        let dict = dictionary(grid_size, chunk_size);
+        let prefix_map = PrefixMap::new(&dict, grid_size, chunk_size, MemoStrategy::Off);
+        // The prefixes of the first 100 words, one to `grid_size - 1` tokens long,
+        // like the column prefixes the search looks up.
+        let prefixes = prefix_map
+            .get_prefix_words(&TokenWord::new())
+            .iter()
+            .take(100)
+            .flat_map(|word| (1..grid_size).map(|len| TokenWord(word.0[..len].to_vec())))
+            .collect::<Vec<_>>();
+        group.bench_with_input(
+            BenchmarkId::from_parameter(size_id(grid_size, chunk_size)),
+            &prefixes,
+            |b, prefixes| {
+                b.iter(|| {
+                    for prefix in prefixes {
+                        black_box(prefix_map.get_prefix_words(prefix));
+                    }
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+pub fn benchmark_search(c: &mut Criterion) {
+    let mut group = c.benchmark_group("full search");
+    for (grid_size, chunk_size) in SIZES {
+        let dict = dictionary(grid_size, chunk_size);
+        let memos = [
+            ("no_table", MemoStrategy::Off),
+            (
+                "table",
+                MemoStrategy::Bounded {
+                    capacity: DEFAULT_MEMO_CAPACITY,
+                },
+            ),
+        ];
+        for (table, memo) in memos {
+            let prefix_map = PrefixMap::new(&dict, grid_size, chunk_size, memo);
+            group.bench_function(
+                BenchmarkId::new(size_id(grid_size, chunk_size), table),
+                |b| b.iter(|| prefix_map.symmetric_words_all(SearchMode::All)),
+            );
+        }
+    }
+    group.finish();
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(10);
+    targets = benchmark_tokenize, benchmark_prefix_map_new, benchmark_get_prefix_words, benchmark_search
+}
+criterion_main!(benches);
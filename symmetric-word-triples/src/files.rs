@@ -0,0 +1,644 @@
+use crate::manifest::{BatchOptions, Manifest, ManifestEntry, ENGINE_VERSION};
+use crate::parser;
+use crate::parser::index::{self, IndexHeader};
+use crate::parser::matrix::TokenMatrix;
+use crate::parser::memo::MemoStrategy;
+use crate::parser::token::TokenWord;
+use crate::parser::wordfilter::WordTupleDict;
+use crate::parser::wordfilter::{PrefixMap, SearchMode, WordFilter};
+use crate::render::render_line;
+use rayon::prelude::*;
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::sync::Mutex;
+use std::time::Instant;
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+/// A dictionary file together with the grid and chunk size to search it with.
+type GridChunkJob = (PathBuf, (usize, usize));
+
+/// Solution counts of one dictionary file for a grid and chunk size.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SolutionCount {
+    pub grid_size: usize,
+    pub chunk_size: usize,
+    pub total: usize,
+    /// Number of solutions per first-row word. Words without solutions are left out.
+    pub per_word: Vec<(String, usize)>,
+}
+
+/// Statistics of one dictionary file, grid and chunk size combination of a directory run.
+/// Written as `{name}_grid{g}_chunk{c}_stats.json` next to the output.
+#[derive(Clone, Debug, Serialize)]
+pub struct RunReport {
+    pub dictionary: PathBuf,
+    pub grid_size: usize,
+    pub chunk_size: usize,
+    /// Words in the dictionary file.
+    pub dictionary_words: usize,
+    /// Words left after `len_filter`.
+    pub filtered_words: usize,
+    /// Words pruned by arc consistency before the search, because they can't be in a square.
+    pub pruned_words: usize,
+    /// Distinct chunks, from `Tokens::len`.
+    pub tokens: usize,
+    pub seeds: usize,
+    pub solutions: usize,
+    /// Calls of `backtrack`.
+    pub nodes: u64,
+    /// Average number of candidates per expanded node, by number of filled rows.
+    pub branching_per_depth: Vec<f64>,
+    /// Share of the prefix lookups answered by the memo table, if the table is used.
+    pub table_hit_rate: Option<f64>,
+    pub wall_time_secs: f64,
+    /// Peak resident memory of the process during the combination, where the OS reports it.
+    pub peak_memory_bytes: Option<u64>,
+}
+
+/// Sizes of a dictionary file before and after `len_filter`.
+type DictionarySize = (usize, usize);
+
+pub fn auto_single_sym_word_sol(
+    dictionary_file: &Path,
+    word: &str,
+    grid_size: usize,
+    chunk_size: usize,
+) -> Result<(), Box<dyn std::error::Error>> {
+    auto_single_sym_word_sol_with(
+        dictionary_file,
+        word,
+        grid_size,
+        chunk_size,
+        SearchMode::All,
+    )
+}
+
+/// Same as `auto_single_sym_word_sol`, but only prints the solutions the search mode asks for.
+pub fn auto_single_sym_word_sol_with(
+    dictionary_file: &Path,
+    word: &str,
+    grid_size: usize,
+    chunk_size: usize,
+    mode: SearchMode,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let memo = MemoStrategy::for_grid_size(grid_size);
+
+    let mut word_dictionary = vec![];
+    parser::file_vec(dictionary_file, &mut word_dictionary)?;
+    parser::len_filter(&mut word_dictionary, grid_size * chunk_size);
+    let prefix_map = PrefixMap::new(&word_dictionary, grid_size, chunk_size, memo);
+
+    println!("Word: {:?}", word);
+    let word = prefix_map.tokenize_word(word);
+    println!("TknWord: {:?}\n", word);
+
+    let solution_set_word = prefix_map.symmetric_words_single_with(word, mode)?;
+
+    let solution_set = solution_set_word
+        .par_iter()
+        .map(|word| prefix_map.stringify_token_matrix((**word).clone()))
+        .collect::<Vec<_>>();
+
+    println!("\nSolutions: ");
+    // Print the solutions.
+    for solution in solution_set {
+        println!("{}", solution);
+    }
+
+    Ok(())
+}
+
+pub fn dir_symmetric_words_range(
+    input_dir: &Path,
+    output_dir: &Path,
+    grid_range: (usize, usize),
+    chunk_size_range: (usize, usize),
+) -> Result<(), Box<dyn std::error::Error>> {
+    dir_symmetric_words_range_with(
+        input_dir,
+        output_dir,
+        grid_range,
+        chunk_size_range,
+        BatchOptions::default(),
+    )
+}
+
+/// Same as `dir_symmetric_words_range`, but with explicit batch options.
+/// Outputs are recorded in the manifest of the output directory, and a combination is only
+/// recomputed if its dictionary, its parameters or the engine version changed, unless forced.
+pub fn dir_symmetric_words_range_with(
+    input_dir: &Path,
+    output_dir: &Path,
+    grid_range: (usize, usize),
+    chunk_size_range: (usize, usize),
+    batch: BatchOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let jobs = dir_grid_chunk_jobs(input_dir, output_dir, grid_range, chunk_size_range)?;
+    let mut manifest = Manifest::load(output_dir)?;
+    let mut input_checksums = HashMap::new();
+    let mut outputs = BTreeSet::new();
+
+    for (path, (grid_size, chunk_size)) in jobs {
+        let (output_dir_path, dir_name) = output_dir_for(output_dir, &path);
+
+        let file = format!(
+            "{dir_name}_grid{}_chunk{}{}",
+            grid_size,
+            chunk_size,
+            batch.render.file_suffix()
+        );
+        let output_file_path = output_dir_path.join(&file);
+        let output = format!("{dir_name}/{file}");
+        outputs.insert(output.clone());
+
+        let input_checksum = match input_checksums.get(&path) {
+            Some(&input_checksum) => input_checksum,
+            None => {
+                let mut word_dictionary = vec![];
+                parser::file_vec(&path, &mut word_dictionary)?;
+                let input_checksum = index::dictionary_checksum(&word_dictionary);
+                input_checksums.insert(path.clone(), input_checksum);
+                input_checksum
+            }
+        };
+        let mut entry = ManifestEntry {
+            input: path.clone(),
+            input_checksum,
+            grid_size,
+            chunk_size,
+            engine_version: ENGINE_VERSION.to_string(),
+            solutions: 0,
+        };
+        if !batch.force && manifest.is_up_to_date(&output, &entry, output_dir) {
+            println!("File \"{file}\" is up to date");
+            continue;
+        }
+
+        std::fs::create_dir(&output_dir_path).ok();
+
+        println!(
+            "File name: {file} Grid: {}, Chunk size: {}",
+            grid_size, chunk_size,
+        );
+
+        reset_peak_memory();
+        let start = Instant::now();
+        let (prefix_map, dictionary_size) =
+            load_or_build_sized(&path, grid_size, chunk_size, batch.index_dir.as_deref())?;
+        let prefix_map = Arc::new(prefix_map);
+        let seeds = prefix_map.get_prefix_words(&TokenWord::new());
+        let mut solution_set = search_seeds(&prefix_map, &seeds, SearchMode::All);
+        let report = run_report(
+            &path,
+            &prefix_map,
+            dictionary_size,
+            solution_set.len(),
+            start,
+        );
+        write_run_report(&report, &output_file_path)?;
+
+        entry.solutions = solution_set.len();
+        if solution_set.is_empty() {
+            // Don't leave the solutions of an older dictionary behind.
+            std::fs::remove_file(&output_file_path).ok();
+        } else {
+            solution_set.sort_by_cached_key(|solution| render_line(solution, prefix_map.tokens()));
+            let document = batch
+                .render
+                .render_document(solution_set.iter().map(Arc::as_ref), prefix_map.tokens());
+            std::fs::write(&output_file_path, document)?;
+        }
+
+        // Saved after every combination, so an interrupted run keeps its progress.
+        manifest.outputs.insert(output, entry);
+        manifest.save(output_dir)?;
+    }
+
+    if batch.clean {
+        let stale = manifest
+            .outputs
+            .keys()
+            .filter(|output| !outputs.contains(*output))
+            .cloned()
+            .collect::<Vec<_>>();
+        for output in stale {
+            let output_file_path = output_dir.join(&output);
+            std::fs::remove_file(&output_file_path).ok();
+            std::fs::remove_file(stats_path(&output_file_path)).ok();
+            manifest.outputs.remove(&output);
+            println!("Removed \"{output}\"");
+        }
+        manifest.save(output_dir)?;
+    }
+    Ok(())
+}
+
+/// Same walk as `dir_symmetric_words_range`, but only counts the solutions.
+/// Writes the per-first-word counts of every combination into the output directory
+/// and prints the totals of all combinations at the end.
+pub fn dir_count_symmetric_words_range(
+    input_dir: &Path,
+    output_dir: &Path,
+    grid_range: (usize, usize),
+    chunk_size_range: (usize, usize),
+) -> Result<Vec<(PathBuf, SolutionCount)>, Box<dyn std::error::Error>> {
+    let mut totals = vec![];
+    for (path, (grid_size, chunk_size)) in
+        dir_grid_chunk_jobs(input_dir, output_dir, grid_range, chunk_size_range)?
+    {
+        let (output_dir_path, dir_name) = output_dir_for(output_dir, &path);
+
+        let file = format!("{dir_name}_grid{}_chunk{}_count.txt", grid_size, chunk_size,);
+        let output_file_path = output_dir_path.join(&file);
+
+        std::fs::create_dir(&output_dir_path).ok();
+
+        println!(
+            "File name: {file} Grid: {}, Chunk size: {}",
+            grid_size, chunk_size,
+        );
+
+        reset_peak_memory();
+        let start = Instant::now();
+        let (prefix_map, dictionary_size) =
+            load_or_build_sized(&path, grid_size, chunk_size, None)?;
+        let prefix_map = Arc::new(prefix_map);
+        let solution_count = count_symmetric_words_of_prefix_map(Arc::clone(&prefix_map));
+        let report = run_report(
+            &path,
+            &prefix_map,
+            dictionary_size,
+            solution_count.total,
+            start,
+        );
+        write_run_report(&report, &output_file_path)?;
+
+        if solution_count.total > 0 {
+            if let Ok(file) = std::fs::File::create(&output_file_path) {
+                let mut file = std::io::BufWriter::new(file);
+                for (word, count) in &solution_count.per_word {
+                    writeln!(file, "{} {}", word, count)?;
+                }
+            }
+        }
+        totals.push((path, solution_count));
+    }
+
+    println!("\nTotals: ");
+    for (path, solution_count) in &totals {
+        println!(
+            "{}: grid size {}, chunk size {}: {} solutions",
+            path.display(),
+            solution_count.grid_size,
+            solution_count.chunk_size,
+            solution_count.total,
+        );
+    }
+    Ok(totals)
+}
+
+/// Returns every (dictionary file, (grid size, chunk size)) combination of a directory run.
+fn dir_grid_chunk_jobs(
+    input_dir: &Path,
+    output_dir: &Path,
+    grid_range: (usize, usize),
+    chunk_size_range: (usize, usize),
+) -> Result<Vec<GridChunkJob>, Box<dyn std::error::Error>> {
+    // Check if the input and output directories exist.
+    if !input_dir.exists() {
+        panic!("Input directory does not exist.");
+    }
+    if !output_dir.exists() {
+        panic!("Output directory does not exist.");
+    }
+    let grid_chunk_iter = (grid_range.0..=grid_range.1)
+        .flat_map(|g| (chunk_size_range.0..=chunk_size_range.1).map(move |c| (g, c)));
+    let jobs = std::fs::read_dir(input_dir)?
+        .filter_map(|p| p.ok())
+        .map(|p| p.path())
+        .flat_map(|path| grid_chunk_iter.clone().map(move |gc| (path.clone(), gc)))
+        .collect();
+    Ok(jobs)
+}
+
+/// Returns the output directory for a dictionary file and the name it is based on.
+fn output_dir_for(output_dir: &Path, path: &Path) -> (PathBuf, String) {
+    let dir_name = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().replace(' ', "_"))
+        .unwrap_or_default();
+    (output_dir.join(&dir_name), dir_name)
+}
+
+#[inline]
+pub fn symmetric_words_in_file_mt(
+    file_path: &Path,
+    grid_size: usize,
+    chunk_size: usize,
+) -> Result<WordTupleDict, Box<dyn std::error::Error>> {
+    symmetric_words_in_file_mt_with(file_path, grid_size, chunk_size, SearchMode::All)
+}
+
+/// Same as `symmetric_words_in_file_mt`, but applies the search mode to every seed word.
+/// In sample mode every seed word gets its own rng stream, so the result does not depend
+/// on the order in which the threads pick up the seeds.
+#[inline]
+pub fn symmetric_words_in_file_mt_with(
+    file_path: &Path,
+    grid_size: usize,
+    chunk_size: usize,
+    mode: SearchMode,
+) -> Result<WordTupleDict, Box<dyn std::error::Error>> {
+    if grid_size == 0 {
+        return Ok(vec![]);
+    }
+    let prefix_map = Arc::new(load_prefix_map(file_path, grid_size, chunk_size)?);
+    Ok(symmetric_words_of_prefix_map(prefix_map, mode))
+}
+
+/// Searches with every word of the prefix map in the first row, in parallel, printing progress.
+fn symmetric_words_of_prefix_map(prefix_map: Arc<PrefixMap>, mode: SearchMode) -> WordTupleDict {
+    let word_dictionary = prefix_map.get_prefix_words(&TokenWord::new());
+    symmetric_words_of_seeds(&prefix_map, &word_dictionary, mode)
+}
+
+/// Searches with every seed word in the first row, in parallel, printing progress.
+pub(crate) fn symmetric_words_of_seeds(
+    prefix_map: &PrefixMap,
+    word_dictionary: &[Arc<TokenWord>],
+    mode: SearchMode,
+) -> WordTupleDict {
+    search_seeds(prefix_map, word_dictionary, mode)
+        .par_iter()
+        .map(|word| prefix_map.stringify_token_matrix((**word).clone()))
+        .collect::<Vec<_>>()
+}
+
+/// Same as `symmetric_words_of_seeds`, but returns the solutions as token matrices.
+fn search_seeds(
+    prefix_map: &PrefixMap,
+    word_dictionary: &[Arc<TokenWord>],
+    mode: SearchMode,
+) -> Vec<Arc<TokenMatrix>> {
+    let (grid_size, chunk_size) = (prefix_map.grid_size(), prefix_map.chunk_size());
+    let size = word_dictionary.len();
+
+    let cur = Arc::new(Mutex::new(0usize));
+    let solution_count = Arc::new(Mutex::new(0));
+    let update_freq = (size / 512).max(1);
+    let solution_set_file = word_dictionary
+        .par_iter()
+        .enumerate()
+        .flat_map_iter(|(i, word)| {
+            let solutions = prefix_map
+                .symmetric_words_single_with((**word).clone(), mode.with_stream(i as u64))
+                .unwrap();
+
+            let mut cur = cur.lock().unwrap();
+            let mut solution_count = solution_count.lock().unwrap();
+            *solution_count += solutions.len();
+            *cur += 1;
+            if (*cur).is_multiple_of(update_freq) {
+                print_status(*cur, size, *solution_count, grid_size, chunk_size);
+            }
+            solutions
+        })
+        .collect::<Vec<_>>();
+    let cur = cur.lock().unwrap();
+    let solution_count = solution_count.lock().unwrap();
+    print_status(*cur, size, *solution_count, grid_size, chunk_size);
+
+    solution_set_file
+}
+
+/// Counts the solutions of a dictionary file without materialising them.
+#[inline]
+pub fn count_symmetric_words_in_file_mt(
+    file_path: &Path,
+    grid_size: usize,
+    chunk_size: usize,
+) -> Result<SolutionCount, Box<dyn std::error::Error>> {
+    if grid_size == 0 {
+        return Ok(SolutionCount {
+            grid_size,
+            chunk_size,
+            total: 0,
+            per_word: vec![],
+        });
+    }
+    let prefix_map = Arc::new(load_prefix_map(file_path, grid_size, chunk_size)?);
+    Ok(count_symmetric_words_of_prefix_map(prefix_map))
+}
+
+/// Counts the solutions of every word of the prefix map, in parallel, printing progress.
+fn count_symmetric_words_of_prefix_map(prefix_map: Arc<PrefixMap>) -> SolutionCount {
+    let (grid_size, chunk_size) = (prefix_map.grid_size(), prefix_map.chunk_size());
+    let word_dictionary = prefix_map.get_prefix_words(&TokenWord::new());
+
+    let size = word_dictionary.len();
+
+    let cur = Arc::new(Mutex::new(0usize));
+    let solution_count = Arc::new(Mutex::new(0));
+    let update_freq = (size / 512).max(1);
+    let mut per_word = word_dictionary
+        .par_iter()
+        .filter_map(|word| {
+            let solutions = prefix_map
+                .count_symmetric_words_single((**word).clone())
+                .unwrap();
+
+            let mut cur = cur.lock().unwrap();
+            let mut solution_count = solution_count.lock().unwrap();
+            *solution_count += solutions;
+            *cur += 1;
+            if (*cur).is_multiple_of(update_freq) {
+                print_status(*cur, size, *solution_count, grid_size, chunk_size);
+            }
+            (solutions > 0).then(|| (prefix_map.stringify_token_word(word), solutions))
+        })
+        .collect::<Vec<_>>();
+    let cur = cur.lock().unwrap();
+    let solution_count = solution_count.lock().unwrap();
+    print_status(*cur, size, *solution_count, grid_size, chunk_size);
+
+    per_word.sort_unstable();
+    SolutionCount {
+        grid_size,
+        chunk_size,
+        total: *solution_count,
+        per_word,
+    }
+}
+
+/// Reads a dictionary file and builds the prefix map for the given grid and chunk size.
+fn load_prefix_map(
+    file_path: &Path,
+    grid_size: usize,
+    chunk_size: usize,
+) -> Result<PrefixMap, Box<dyn std::error::Error>> {
+    let memo = MemoStrategy::for_grid_size(grid_size);
+
+    // Make a dictionary out of the file.
+    let mut word_dictionary = vec![];
+    parser::file_vec(file_path, &mut word_dictionary)?;
+    parser::len_filter(&mut word_dictionary, grid_size * chunk_size);
+
+    Ok(PrefixMap::new(
+        &word_dictionary,
+        grid_size,
+        chunk_size,
+        memo,
+    ))
+}
+
+/// Loads the prefix map of a dictionary file from its index in `index_dir`.
+/// Builds the prefix map and writes the index if there is none yet, or if it is of another
+/// format version or the dictionary changed since it was written.
+pub fn load_or_build_prefix_map(
+    file_path: &Path,
+    grid_size: usize,
+    chunk_size: usize,
+    index_dir: &Path,
+) -> Result<PrefixMap, Box<dyn std::error::Error>> {
+    load_or_build_sized(file_path, grid_size, chunk_size, Some(index_dir))
+        .map(|(prefix_map, _)| prefix_map)
+}
+
+/// Same as `load_or_build_prefix_map`, but also returns the size of the dictionary.
+/// Without `index_dir` the prefix map is built and no index is read or written.
+pub(crate) fn load_or_build_sized(
+    file_path: &Path,
+    grid_size: usize,
+    chunk_size: usize,
+    index_dir: Option<&Path>,
+) -> Result<(PrefixMap, DictionarySize), Box<dyn std::error::Error>> {
+    let memo = MemoStrategy::for_grid_size(grid_size);
+
+    let mut word_dictionary = vec![];
+    parser::file_vec(file_path, &mut word_dictionary)?;
+    let word_len = grid_size * chunk_size;
+    let dictionary_size = (
+        word_dictionary.len(),
+        word_dictionary
+            .iter()
+            .filter(|word| word.len() == word_len)
+            .count(),
+    );
+    let Some(index_dir) = index_dir else {
+        parser::len_filter(&mut word_dictionary, word_len);
+        let prefix_map = PrefixMap::new(&word_dictionary, grid_size, chunk_size, memo);
+        return Ok((prefix_map, dictionary_size));
+    };
+    let expected = IndexHeader::new(
+        grid_size,
+        chunk_size,
+        index::dictionary_checksum(&word_dictionary),
+    );
+
+    let index_path = index_path(index_dir, file_path, grid_size, chunk_size);
+    if let Ok(file) = File::open(&index_path) {
+        match PrefixMap::read_index(BufReader::new(file), &expected, memo) {
+            Ok(prefix_map) => return Ok((prefix_map, dictionary_size)),
+            Err(error) => println!("Rebuilding \"{}\": {error}", index_path.display()),
+        }
+    }
+
+    parser::len_filter(&mut word_dictionary, word_len);
+    let prefix_map = PrefixMap::new(&word_dictionary, grid_size, chunk_size, memo);
+
+    // Write to a temporary file first, so a cancelled run never leaves a broken index behind.
+    std::fs::create_dir_all(index_dir)?;
+    let tmp_path = index_path.with_extension("idx.tmp");
+    prefix_map.write_index(
+        BufWriter::new(File::create(&tmp_path)?),
+        expected.source_checksum,
+    )?;
+    std::fs::rename(&tmp_path, &index_path)?;
+    Ok((prefix_map, dictionary_size))
+}
+
+/// The path of the index of a dictionary file for the grid and chunk size in `index_dir`.
+pub fn index_path(
+    index_dir: &Path,
+    file_path: &Path,
+    grid_size: usize,
+    chunk_size: usize,
+) -> PathBuf {
+    let (_, dir_name) = output_dir_for(index_dir, file_path);
+    index_dir.join(format!("{dir_name}_grid{grid_size}_chunk{chunk_size}.idx"))
+}
+
+fn run_report(
+    dictionary: &Path,
+    prefix_map: &PrefixMap,
+    (dictionary_words, filtered_words): DictionarySize,
+    solutions: usize,
+    start: Instant,
+) -> RunReport {
+    let stats = prefix_map.stats();
+    RunReport {
+        dictionary: dictionary.to_path_buf(),
+        grid_size: prefix_map.grid_size(),
+        chunk_size: prefix_map.chunk_size(),
+        dictionary_words,
+        filtered_words,
+        pruned_words: prefix_map.pruned_words(),
+        tokens: prefix_map.tokens().len() as usize,
+        seeds: prefix_map.get_prefix_words(&TokenWord::new()).len(),
+        solutions,
+        nodes: stats.nodes,
+        branching_per_depth: stats.branching_per_depth(),
+        table_hit_rate: stats.table_hit_rate(),
+        wall_time_secs: start.elapsed().as_secs_f64(),
+        peak_memory_bytes: peak_memory_bytes(),
+    }
+}
+
+/// The path of the report of an output file, `{output}_stats.json`.
+fn stats_path(output_file_path: &Path) -> PathBuf {
+    let stem = output_file_path
+        .file_stem()
+        .unwrap_or_default()
+        .to_string_lossy();
+    output_file_path.with_file_name(format!("{stem}_stats.json"))
+}
+
+fn write_run_report(report: &RunReport, output_file_path: &Path) -> std::io::Result<()> {
+    let json = serde_json::to_string_pretty(report)?;
+    std::fs::write(stats_path(output_file_path), json)
+}
+
+/// Resets the peak resident memory of the process, so the next reading covers only what
+/// follows. Only Linux supports this, elsewhere the peak covers the whole process.
+fn reset_peak_memory() {
+    std::fs::write("/proc/self/clear_refs", "5").ok();
+}
+
+/// The peak resident memory of the process in bytes, from `/proc/self/status` on Linux.
+fn peak_memory_bytes() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmHWM:"))?;
+    let kilobytes = line.split_whitespace().nth(1)?.parse::<u64>().ok()?;
+    Some(kilobytes * 1024)
+}
+
+fn print_status(
+    cur: usize,
+    size: usize,
+    solution_count: usize,
+    grid_size: usize,
+    chunk_size: usize,
+) {
+    println!(
+        "\x1b[1A\x1b[2K    Finished {:.2}% of file. {} solutions found with grid size {} and chunk size {}",
+        (cur as f64 / size as f64) * 100.0,
+        solution_count,
+        grid_size,
+        chunk_size,
+    );
+}
@@ -0,0 +1,217 @@
+//! The co-occurrence graph of a solution set: every word of a solution is a node, and two
+//! words are connected by an edge counting the solutions they are both in.
+
+#[cfg(feature = "native")]
+use crate::parser::{
+    matrix::TokenMatrix,
+    token::{TknSize, Token, TokenWord},
+    wordfilter::PrefixMap,
+};
+use crate::render::xml_escape;
+use std::collections::BTreeMap;
+#[cfg(feature = "native")]
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::str::FromStr;
+
+/// Output formats of `CooccurrenceGraph::write`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GraphFormat {
+    Dot,
+    GraphMl,
+    /// An edge list with the columns `source,target,weight`.
+    Csv,
+}
+
+impl FromStr for GraphFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<GraphFormat, String> {
+        match s {
+            "dot" => Ok(GraphFormat::Dot),
+            "graphml" => Ok(GraphFormat::GraphMl),
+            "csv" => Ok(GraphFormat::Csv),
+            _ => Err(format!(
+                "Unknown graph format \"{s}\", expected dot, graphml or csv."
+            )),
+        }
+    }
+}
+
+impl GraphFormat {
+    /// The file extension of the format, without the dot.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            GraphFormat::Dot => "dot",
+            GraphFormat::GraphMl => "graphml",
+            GraphFormat::Csv => "csv",
+        }
+    }
+}
+
+/// Words with the number of solutions they are in, and undirected edges between words with
+/// the number of solutions they share. A word repeated in a solution counts once, and no
+/// word has an edge to itself.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CooccurrenceGraph {
+    pub nodes: BTreeMap<String, usize>,
+    /// Keyed by the two words in order.
+    pub edges: BTreeMap<(String, String), usize>,
+}
+
+impl CooccurrenceGraph {
+    /// Adds the rows of one solution.
+    pub fn add_solution<S: AsRef<str>>(&mut self, rows: &[S]) {
+        let mut words = rows.iter().map(AsRef::as_ref).collect::<Vec<_>>();
+        words.sort_unstable();
+        words.dedup();
+        for (i, &word) in words.iter().enumerate() {
+            *self.nodes.entry(word.to_string()).or_default() += 1;
+            for &other in &words[i + 1..] {
+                *self
+                    .edges
+                    .entry((word.to_string(), other.to_string()))
+                    .or_default() += 1;
+            }
+        }
+    }
+
+    pub fn write(&self, format: GraphFormat) -> String {
+        match format {
+            GraphFormat::Dot => self.to_dot(),
+            GraphFormat::GraphMl => self.to_graphml(),
+            GraphFormat::Csv => self.to_csv(),
+        }
+    }
+
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("graph cooccurrence {\n");
+        for (word, solutions) in &self.nodes {
+            writeln!(dot, "  {} [solutions={solutions}];", dot_id(word)).unwrap();
+        }
+        for ((source, target), weight) in &self.edges {
+            writeln!(
+                dot,
+                "  {} -- {} [weight={weight}];",
+                dot_id(source),
+                dot_id(target)
+            )
+            .unwrap();
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    pub fn to_graphml(&self) -> String {
+        let mut xml = String::from(concat!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+            "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n",
+            "  <key id=\"solutions\" for=\"node\" attr.name=\"solutions\" attr.type=\"int\"/>\n",
+            "  <key id=\"weight\" for=\"edge\" attr.name=\"weight\" attr.type=\"int\"/>\n",
+            "  <graph id=\"cooccurrence\" edgedefault=\"undirected\">\n",
+        ));
+        for (word, solutions) in &self.nodes {
+            writeln!(
+                xml,
+                "    <node id=\"{}\"><data key=\"solutions\">{solutions}</data></node>",
+                xml_escape(word)
+            )
+            .unwrap();
+        }
+        for ((source, target), weight) in &self.edges {
+            writeln!(
+                xml,
+                "    <edge source=\"{}\" target=\"{}\"><data key=\"weight\">{weight}</data></edge>",
+                xml_escape(source),
+                xml_escape(target)
+            )
+            .unwrap();
+        }
+        xml.push_str("  </graph>\n</graphml>\n");
+        xml
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("source,target,weight\n");
+        for ((source, target), weight) in &self.edges {
+            writeln!(csv, "{},{},{weight}", csv_field(source), csv_field(target)).unwrap();
+        }
+        csv
+    }
+}
+
+fn dot_id(word: &str) -> String {
+    format!("\"{}\"", word.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn csv_field(word: &str) -> String {
+    if word.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", word.replace('"', "\"\""))
+    } else {
+        word.to_string()
+    }
+}
+
+/// `CooccurrenceGraph` over token words, filled while searching. Only the words of the
+/// finished graph are turned into strings.
+#[cfg(feature = "native")]
+#[derive(Default)]
+pub(crate) struct TokenCooccurrences {
+    nodes: HashMap<TokenWord, usize>,
+    edges: HashMap<(TokenWord, TokenWord), usize>,
+}
+
+#[cfg(feature = "native")]
+impl TokenCooccurrences {
+    pub(crate) fn add(&mut self, solution: &TokenMatrix) {
+        let mut words = solution.rows().collect::<Vec<_>>();
+        words.sort_unstable_by(|a, b| token_numbers(a).cmp(token_numbers(b)));
+        words.dedup();
+        for (i, &word) in words.iter().enumerate() {
+            *self.nodes.entry(TokenWord(word.to_vec())).or_default() += 1;
+            for &other in &words[i + 1..] {
+                *self
+                    .edges
+                    .entry((TokenWord(word.to_vec()), TokenWord(other.to_vec())))
+                    .or_default() += 1;
+            }
+        }
+    }
+
+    pub(crate) fn merge(mut self, other: TokenCooccurrences) -> TokenCooccurrences {
+        for (word, count) in other.nodes {
+            *self.nodes.entry(word).or_default() += count;
+        }
+        for (edge, count) in other.edges {
+            *self.edges.entry(edge).or_default() += count;
+        }
+        self
+    }
+
+    pub(crate) fn stringify(&self, prefix_map: &PrefixMap) -> CooccurrenceGraph {
+        let word = |tkn_word: &TokenWord| prefix_map.stringify_token_word(tkn_word);
+        let nodes = self
+            .nodes
+            .iter()
+            .map(|(tkn_word, &count)| (word(tkn_word), count))
+            .collect();
+        let edges = self
+            .edges
+            .iter()
+            .map(|((source, target), &count)| {
+                let (source, target) = (word(source), word(target));
+                if source <= target {
+                    ((source, target), count)
+                } else {
+                    ((target, source), count)
+                }
+            })
+            .collect();
+        CooccurrenceGraph { nodes, edges }
+    }
+}
+
+#[cfg(feature = "native")]
+fn token_numbers(word: &[Token]) -> impl Iterator<Item = TknSize> + '_ {
+    word.iter().map(|tkn| tkn.0)
+}
@@ -0,0 +1,346 @@
+use crate::files::load_or_build_sized;
+use crate::parser::token::TokenWord;
+use crate::parser::wordfilter::{PrefixMap, SearchMode, WordFilter};
+use crate::render::RenderStyle;
+use rayon::prelude::*;
+use serde::Deserialize;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// A batch description with different parameters per dictionary, read from TOML or JSON.
+///
+/// ```toml
+/// [[jobs]]
+/// dictionary = "input/words_alpha.txt"
+/// grid = [3, 4]
+/// chunk = 3
+/// words = ["gladiator"]
+/// limit = 100
+/// format = "json"
+/// output = "output/nightly"
+/// index_dir = "output/index"
+/// ```
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct JobFile {
+    pub jobs: Vec<Job>,
+}
+
+/// One dictionary searched with every grid and chunk size of the ranges.
+/// Relative paths are relative to the job file.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Job {
+    pub dictionary: PathBuf,
+    pub grid: SizeRange,
+    pub chunk: SizeRange,
+    /// Only search squares with one of these words in the first row. All words if empty.
+    /// A combination fails if one of them is not a word of its grid and chunk size.
+    #[serde(default)]
+    pub words: Vec<String>,
+    /// Stop after this many solutions per first-row word.
+    pub limit: Option<usize>,
+    /// Sample the solutions randomly with this seed instead of taking the first ones.
+    pub sample_seed: Option<u64>,
+    #[serde(default)]
+    pub format: OutputFormat,
+    /// How the `lines` format writes the solutions.
+    #[serde(default)]
+    pub render: RenderStyle,
+    /// The directory the outputs are written to.
+    pub output: PathBuf,
+    /// Where the prefix map indexes are cached between runs. No indexes are written
+    /// without it.
+    pub index_dir: Option<PathBuf>,
+}
+
+/// A single size or an inclusive `[start, end]` range.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(untagged)]
+pub enum SizeRange {
+    Single(usize),
+    Range(usize, usize),
+}
+
+impl SizeRange {
+    pub fn bounds(&self) -> (usize, usize) {
+        match *self {
+            SizeRange::Single(size) => (size, size),
+            SizeRange::Range(start, end) => (start, end),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    /// One solution per line, like the directory runs. `{name}_grid{g}_chunk{c}.txt`
+    #[default]
+    Lines,
+    /// An array of solutions, each an array of its rows. `{name}_grid{g}_chunk{c}.json`
+    Json,
+    /// The number of solutions per first-row word. `{name}_grid{g}_chunk{c}_count.txt`
+    Count,
+}
+
+/// The outcome of one grid and chunk size combination of a job.
+#[derive(Clone, Debug)]
+pub struct JobReport {
+    pub dictionary: PathBuf,
+    pub grid_size: usize,
+    pub chunk_size: usize,
+    pub solutions: usize,
+    /// The written file, if there were solutions.
+    pub output: Option<PathBuf>,
+    pub elapsed: Duration,
+    pub error: Option<String>,
+}
+
+impl JobFile {
+    /// Reads a job file. Files ending in `.toml` are TOML, everything else JSON.
+    pub fn load(path: &Path) -> Result<JobFile, Box<dyn std::error::Error>> {
+        let text = std::fs::read_to_string(path)?;
+        let job_file = if path
+            .extension()
+            .is_some_and(|extension| extension == "toml")
+        {
+            toml::from_str(&text)?
+        } else {
+            serde_json::from_str(&text)?
+        };
+        Ok(job_file)
+    }
+
+    /// Checks the parameters of every job before anything runs.
+    pub fn validate(&self) -> Result<(), String> {
+        for (i, job) in self.jobs.iter().enumerate() {
+            for (name, range) in [("grid", job.grid), ("chunk", job.chunk)] {
+                let (start, end) = range.bounds();
+                if start == 0 || start > end {
+                    return Err(format!(
+                        "Job {}: invalid {name} range {start}..={end}.",
+                        i + 1
+                    ));
+                }
+            }
+            if job.limit == Some(0) {
+                return Err(format!("Job {}: the limit has to be at least 1.", i + 1));
+            }
+            if job.render != RenderStyle::Line && job.format != OutputFormat::Lines {
+                return Err(format!(
+                    "Job {}: render only works with the lines format.",
+                    i + 1
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs every combination of every job, resolving relative paths against `base_dir`.
+    /// A failing combination is reported and the run goes on with the next one.
+    pub fn run(&self, base_dir: &Path) -> Vec<JobReport> {
+        let mut reports = vec![];
+        for job in &self.jobs {
+            let dictionary = base_dir.join(&job.dictionary);
+            let output_dir = base_dir.join(&job.output);
+            let index_dir = job
+                .index_dir
+                .as_ref()
+                .map(|index_dir| base_dir.join(index_dir));
+            let (grid_start, grid_end) = job.grid.bounds();
+            let (chunk_start, chunk_end) = job.chunk.bounds();
+            for grid_size in grid_start..=grid_end {
+                for chunk_size in chunk_start..=chunk_end {
+                    println!(
+                        "{}: grid size {grid_size}, chunk size {chunk_size}",
+                        dictionary.display()
+                    );
+                    let start = Instant::now();
+                    let result = job.run_single(
+                        &dictionary,
+                        &output_dir,
+                        index_dir.as_deref(),
+                        grid_size,
+                        chunk_size,
+                    );
+                    let (solutions, output, error) = match result {
+                        Ok((solutions, output)) => (solutions, output, None),
+                        Err(error) => (0, None, Some(error.to_string())),
+                    };
+                    reports.push(JobReport {
+                        dictionary: dictionary.clone(),
+                        grid_size,
+                        chunk_size,
+                        solutions,
+                        output,
+                        elapsed: start.elapsed(),
+                        error,
+                    });
+                }
+            }
+        }
+        reports
+    }
+}
+
+impl Job {
+    fn mode(&self) -> SearchMode {
+        match (self.limit, self.sample_seed) {
+            (None, None) => SearchMode::All,
+            (Some(limit), None) => SearchMode::First { limit },
+            (limit, Some(seed)) => SearchMode::Sample {
+                limit: limit.unwrap_or(usize::MAX),
+                seed,
+                stream: 0,
+            },
+        }
+    }
+
+    /// The first-row words to search. The given words are sorted, so samples are reproducible.
+    /// Fails with the words that aren't words of the dictionary for this grid and chunk size.
+    fn seeds(&self, prefix_map: &PrefixMap) -> Result<Vec<Arc<TokenWord>>, String> {
+        if self.words.is_empty() {
+            return Ok(prefix_map.get_prefix_words(&TokenWord::new()));
+        }
+        let mut seeds = vec![];
+        let mut unknown = vec![];
+        for word in &self.words {
+            match prefix_map
+                .try_tokenize_word(word)
+                .filter(|tkn_word| prefix_map.contains(tkn_word))
+            {
+                Some(tkn_word) => seeds.push(Arc::new(tkn_word)),
+                None => unknown.push(format!("\"{word}\"")),
+            }
+        }
+        if !unknown.is_empty() {
+            return Err(format!(
+                "Not words of the dictionary: {}.",
+                unknown.join(", ")
+            ));
+        }
+        seeds.sort_unstable_by_key(|seed| prefix_map.stringify_token_word(seed));
+        seeds.dedup();
+        Ok(seeds)
+    }
+
+    /// Searches one combination and writes its output. Returns the number of solutions
+    /// and the written file.
+    fn run_single(
+        &self,
+        dictionary: &Path,
+        output_dir: &Path,
+        index_dir: Option<&Path>,
+        grid_size: usize,
+        chunk_size: usize,
+    ) -> Result<(usize, Option<PathBuf>), Box<dyn std::error::Error>> {
+        let name = dictionary
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().replace(' ', "_"))
+            .unwrap_or_default();
+        let (prefix_map, _) = load_or_build_sized(dictionary, grid_size, chunk_size, index_dir)?;
+        let seeds = self.seeds(&prefix_map)?;
+        let mode = self.mode();
+
+        let file_name = match self.format {
+            OutputFormat::Lines => format!(
+                "{name}_grid{grid_size}_chunk{chunk_size}{}",
+                self.render.file_suffix()
+            ),
+            OutputFormat::Json => format!("{name}_grid{grid_size}_chunk{chunk_size}.json"),
+            OutputFormat::Count => format!("{name}_grid{grid_size}_chunk{chunk_size}_count.txt"),
+        };
+        let output_path = output_dir.join(file_name);
+        std::fs::create_dir_all(output_dir)?;
+
+        if self.format == OutputFormat::Count {
+            let per_word = seeds
+                .par_iter()
+                .map(|seed| {
+                    let count = prefix_map.count_symmetric_words_single((**seed).clone())?;
+                    Ok((prefix_map.stringify_token_word(seed), count))
+                })
+                .collect::<Result<Vec<_>, String>>()?;
+            let solutions = per_word.iter().map(|(_, count)| count).sum();
+            if solutions == 0 {
+                return Ok((0, None));
+            }
+            let mut file = BufWriter::new(File::create(&output_path)?);
+            for (word, count) in per_word.iter().filter(|(_, count)| *count > 0) {
+                writeln!(file, "{word} {count}")?;
+            }
+            file.flush()?;
+            return Ok((solutions, Some(output_path)));
+        }
+
+        let mut solutions = seeds
+            .par_iter()
+            .enumerate()
+            .map(|(i, seed)| {
+                prefix_map.symmetric_words_single_with((**seed).clone(), mode.with_stream(i as u64))
+            })
+            .collect::<Result<Vec<_>, String>>()?
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>();
+        solutions.sort_by_cached_key(|solution| prefix_map.stringify_token_rows(solution));
+        if solutions.is_empty() {
+            return Ok((0, None));
+        }
+
+        let mut file = BufWriter::new(File::create(&output_path)?);
+        match self.format {
+            OutputFormat::Json => {
+                let rows = solutions
+                    .iter()
+                    .map(|solution| prefix_map.stringify_token_rows(solution))
+                    .collect::<Vec<_>>();
+                serde_json::to_writer(&mut file, &rows)?
+            }
+            _ => file.write_all(
+                self.render
+                    .render_document(solutions.iter().map(Arc::as_ref), prefix_map.tokens())
+                    .as_bytes(),
+            )?,
+        }
+        file.flush()?;
+        Ok((solutions.len(), Some(output_path)))
+    }
+}
+
+/// Prints one line per combination and the totals.
+pub fn print_summary(reports: &[JobReport]) {
+    println!("\nSummary:");
+    for report in reports {
+        let outcome = match (&report.error, &report.output) {
+            (Some(error), _) => format!("failed: {error}"),
+            (None, Some(output)) => {
+                format!("{} solutions -> {}", report.solutions, output.display())
+            }
+            (None, None) => "no solutions".to_string(),
+        };
+        println!(
+            "{}: grid size {}, chunk size {}: {outcome} ({:.2?})",
+            report.dictionary.display(),
+            report.grid_size,
+            report.chunk_size,
+            report.elapsed,
+        );
+    }
+    let failed = reports
+        .iter()
+        .filter(|report| report.error.is_some())
+        .count();
+    let solutions = reports.iter().map(|report| report.solutions).sum::<usize>();
+    let elapsed = reports
+        .iter()
+        .map(|report| report.elapsed)
+        .sum::<Duration>();
+    println!(
+        "{} combinations, {failed} failed, {solutions} solutions in {elapsed:.2?}",
+        reports.len()
+    );
+}
@@ -1,7 +1,35 @@
-use std::path::Path;
-use symmetric_word_triples::{auto_single_sym_word_sol, dir_symmetric_words_range};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use symmetric_word_triples::manifest::BatchOptions;
+use symmetric_word_triples::parser;
+use symmetric_word_triples::render::RenderStyle;
+use symmetric_word_triples::repl::Repl;
+use symmetric_word_triples::{
+    auto_single_sym_word_sol, dir_symmetric_words_range, dir_symmetric_words_range_with,
+    index_path, load_or_build_prefix_map,
+};
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    match args.first().map(String::as_str) {
+        None => run_default(),
+        #[cfg(feature = "server")]
+        Some("serve") => serve(&Options::parse(&args[1..])?),
+        Some("repl") => repl(&Options::parse(&args[1..])?),
+        Some("batch") => batch(&Options::parse(&args[1..])?),
+        Some("job") => job(&Options::parse(&args[1..])?),
+        Some("index") => index(&Options::parse(&args[1..])?),
+        Some("search") => search(&Options::parse(&args[1..])?),
+        Some("shard") => shard(&Options::parse(&args[1..])?),
+        Some("merge") => merge(&Options::parse(&args[1..])?),
+        Some("verify") => verify(&Options::parse(&args[1..])?),
+        Some("graph") => graph(&Options::parse(&args[1..])?),
+        Some("crossword") => crossword(&Options::parse(&args[1..])?),
+        Some(command) => Err(format!("Unknown command \"{command}\".").into()),
+    }
+}
+
+fn run_default() -> Result<(), Box<dyn std::error::Error>> {
     let text_dir = Path::new("./data");
     let grid_range = (3, 3);
     let chunk_size_range = (3, 3);
@@ -13,3 +41,458 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
+
+/// `serve --dict name=path [--dict name=path ...] [--addr host:port] [--timeout-ms ms] [--max-results n]`
+/// `[--max-grid n] [--max-chunk n] [--workers n]`
+#[cfg(feature = "server")]
+fn serve(options: &Options) -> Result<(), Box<dyn std::error::Error>> {
+    use symmetric_word_triples::server::{QueryServer, ServerConfig};
+
+    let default = ServerConfig::default();
+    let config = ServerConfig {
+        addr: options.get("addr").unwrap_or(&default.addr).to_string(),
+        timeout: std::time::Duration::from_millis(
+            options.parse_or("timeout-ms", default.timeout.as_millis() as u64)?,
+        ),
+        max_results: options.parse_or("max-results", default.max_results)?,
+        max_grid_size: options.parse_or("max-grid", default.max_grid_size)?,
+        max_chunk_size: options.parse_or("max-chunk", default.max_chunk_size)?,
+        workers: options.parse_or("workers", default.workers)?,
+    };
+    if config.workers == 0 {
+        return Err("--workers has to be at least 1.".into());
+    }
+    let mut query_server = QueryServer::new(config);
+
+    let dictionaries = options.get_all("dict");
+    if dictionaries.is_empty() {
+        return Err("At least one --dict name=path is needed.".into());
+    }
+    for dictionary in dictionaries {
+        let (name, path) = dictionary
+            .split_once('=')
+            .ok_or_else(|| format!("Expected --dict name=path, got \"{dictionary}\"."))?;
+        println!("Loading dictionary \"{name}\" from {path}");
+        query_server.load_dictionary(name, Path::new(path))?;
+    }
+
+    query_server
+        .serve()
+        .map_err(|error| error.to_string().into())
+}
+
+/// `repl --dict path [--grid n] [--chunk n]`
+fn repl(options: &Options) -> Result<(), Box<dyn std::error::Error>> {
+    let path = options.get("dict").ok_or("--dict path is needed.")?;
+    let mut word_dictionary = vec![];
+    parser::file_vec(Path::new(path), &mut word_dictionary)?;
+    println!(
+        "Loaded {} words from {path}. Type \"help\" for the commands.",
+        word_dictionary.len()
+    );
+
+    let mut repl = Repl::new(
+        word_dictionary,
+        options.parse_or("grid", 3)?,
+        options.parse_or("chunk", 3)?,
+    );
+    repl.run(std::io::stdin().lock(), std::io::stdout())?;
+    Ok(())
+}
+
+/// `batch --input dir --output dir [--grid n|a-b] [--chunk n|a-b] [--force] [--clean] [--render style] [--index-dir dir]`
+fn batch(options: &Options) -> Result<(), Box<dyn std::error::Error>> {
+    let input_dir = Path::new(options.get("input").ok_or("--input dir is needed.")?);
+    let output_dir = Path::new(options.get("output").ok_or("--output dir is needed.")?);
+    let batch = BatchOptions {
+        force: options.flag("force"),
+        clean: options.flag("clean"),
+        render: options.parse_or("render", RenderStyle::Line)?,
+        index_dir: options.get("index-dir").map(PathBuf::from),
+    };
+    dir_symmetric_words_range_with(
+        input_dir,
+        output_dir,
+        options.range_or("grid", (3, 3))?,
+        options.range_or("chunk", (3, 3))?,
+        batch,
+    )
+}
+
+/// `job --file jobs.toml`
+fn job(options: &Options) -> Result<(), Box<dyn std::error::Error>> {
+    use symmetric_word_triples::job::{self, JobFile};
+
+    let path = Path::new(options.get("file").ok_or("--file path is needed.")?);
+    let job_file = JobFile::load(path)?;
+    job_file.validate()?;
+    let reports = job_file.run(path.parent().unwrap_or(Path::new(".")));
+    job::print_summary(&reports);
+
+    let failed = reports
+        .iter()
+        .filter(|report| report.error.is_some())
+        .count();
+    if failed > 0 {
+        return Err(format!("{failed} combinations failed.").into());
+    }
+    Ok(())
+}
+
+/// `index --dict path [--grid n] [--chunk n] [--out dir]`
+fn index(options: &Options) -> Result<(), Box<dyn std::error::Error>> {
+    let path = Path::new(options.get("dict").ok_or("--dict path is needed.")?);
+    let index_dir = Path::new(options.get("out").unwrap_or("."));
+    let grid_size = options.parse_or("grid", 3)?;
+    let chunk_size = options.parse_or("chunk", 3)?;
+
+    let prefix_map = load_or_build_prefix_map(path, grid_size, chunk_size, index_dir)?;
+    println!(
+        "{}: {} words, {} pruned, {} tokens",
+        index_path(index_dir, path, grid_size, chunk_size).display(),
+        prefix_map.get_prefix_words(&Default::default()).len(),
+        prefix_map.pruned_words(),
+        prefix_map.tokens().len(),
+    );
+    Ok(())
+}
+
+/// `search --index path [--word w [--anywhere]] [--word w ...] [--mismatches k] [--render style]`
+/// Searches a memory-mapped index, so several processes can share one index in memory.
+/// With `--anywhere` the word can be in any row instead of only the first one.
+/// With several words every solution contains all of them, in any rows.
+/// With `--mismatches k` up to k cells may differ from their mirror cell, which are listed
+/// in lines and marked in the other styles.
+/// `--render text|html|svg` prints the solutions as chunk grids instead of lines.
+fn search(options: &Options) -> Result<(), Box<dyn std::error::Error>> {
+    use symmetric_word_triples::parser::memo::{MemoStrategy, DEFAULT_MEMO_CAPACITY};
+    use symmetric_word_triples::parser::wordfilter::{PrefixMap, SearchMode, WordFilter};
+
+    let render = options.parse_or("render", RenderStyle::Line)?;
+
+    let path = Path::new(options.get("index").ok_or("--index path is needed.")?);
+    let (header, prefix_map) = PrefixMap::map_index(
+        path,
+        MemoStrategy::Auto {
+            capacity: DEFAULT_MEMO_CAPACITY,
+        },
+    )?;
+    let words = options
+        .get_all("word")
+        .into_iter()
+        .map(|word| {
+            prefix_map
+                .try_tokenize_word(word)
+                .filter(|tkn_word| prefix_map.contains(tkn_word))
+                .ok_or_else(|| format!("\"{word}\" is not a word of the index."))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if let Some(max_mismatches) = options.get("mismatches") {
+        let max_mismatches = max_mismatches
+            .parse()
+            .map_err(|_| format!("Invalid value \"{max_mismatches}\" for --mismatches."))?;
+        let mut near_misses = match words.as_slice() {
+            [] => prefix_map.near_symmetric_words_all(max_mismatches, SearchMode::All),
+            [word] if !options.flag("anywhere") => prefix_map.near_symmetric_words_single(
+                word.clone(),
+                max_mismatches,
+                SearchMode::All,
+            )?,
+            _ => return Err("--mismatches only works with one first-row --word.".into()),
+        };
+        near_misses.sort_by_cached_key(|near_miss| {
+            (
+                prefix_map.stringify_token_matrix(near_miss.square.clone()),
+                near_miss.mismatches.clone(),
+            )
+        });
+        print!(
+            "{}",
+            render.render_near_miss_document(&near_misses, prefix_map.tokens())
+        );
+        eprintln!(
+            "{} squares with up to {max_mismatches} mismatches",
+            near_misses.len()
+        );
+        return Ok(());
+    }
+
+    let solution_set = match words.as_slice() {
+        [] => prefix_map.symmetric_words_all(SearchMode::All),
+        [word] if !options.flag("anywhere") => prefix_map.symmetric_words_single(word.clone())?,
+        words => prefix_map.symmetric_words_containing_all(words, SearchMode::All)?,
+    };
+
+    let mut solutions = solution_set
+        .iter()
+        .map(|solution| {
+            (
+                prefix_map.stringify_token_matrix((**solution).clone()),
+                solution,
+            )
+        })
+        .collect::<Vec<_>>();
+    solutions.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+    print!(
+        "{}",
+        render.render_document(
+            solutions.iter().map(|(_, solution)| solution.as_ref()),
+            prefix_map.tokens()
+        )
+    );
+    eprintln!(
+        "{} solutions with grid size {} and chunk size {}",
+        solutions.len(),
+        header.grid_size,
+        header.chunk_size
+    );
+    Ok(())
+}
+
+/// `shard --dict path --out dir --shard i/n [--by range|hash] [--grid n] [--chunk n] [--render style]`
+/// Searches one of `n` shards of the seed words. `merge` combines the shards afterwards.
+/// `--render text|html|svg` also writes the solutions of the shard in that style.
+fn shard(options: &Options) -> Result<(), Box<dyn std::error::Error>> {
+    use symmetric_word_triples::shard::{self, Shard};
+
+    let path = Path::new(options.get("dict").ok_or("--dict path is needed.")?);
+    let output_dir = Path::new(options.get("out").ok_or("--out dir is needed.")?);
+    let shard = Shard::parse(
+        options.get("shard").ok_or("--shard i/n is needed.")?,
+        options.parse_or("by", shard::ShardBy::Range)?,
+    )?;
+    let report = shard::run_shard(
+        path,
+        output_dir,
+        options.parse_or("grid", 3)?,
+        options.parse_or("chunk", 3)?,
+        shard,
+        options.parse_or("render", RenderStyle::Line)?,
+    )?;
+    println!(
+        "Shard {} of {}: {} of {} seed words, {} solutions -> {}",
+        report.shard,
+        report.shard_count,
+        report.seeds,
+        report.total_seeds,
+        report.solutions,
+        output_dir.join(&report.output).display()
+    );
+    Ok(())
+}
+
+/// `merge --shards dir --out file [--render style]`
+/// `--render text|html|svg` writes the merged solutions as chunk grids instead of lines.
+fn merge(options: &Options) -> Result<(), Box<dyn std::error::Error>> {
+    let shard_dir = Path::new(options.get("shards").ok_or("--shards dir is needed.")?);
+    let output = Path::new(options.get("out").ok_or("--out file is needed.")?);
+    let render = options.parse_or("render", RenderStyle::Line)?;
+    let solutions = symmetric_word_triples::shard::merge_shards(shard_dir, output, render)?;
+    println!("{solutions} solutions -> {}", output.display());
+    Ok(())
+}
+
+/// `verify --output file --dict path [--grid n] [--chunk n] [--rerun]`
+/// Checks every line of an output file, and with `--rerun` searches again for missing lines.
+fn verify(options: &Options) -> Result<(), Box<dyn std::error::Error>> {
+    let output = Path::new(options.get("output").ok_or("--output file is needed.")?);
+    let dictionary = Path::new(options.get("dict").ok_or("--dict path is needed.")?);
+    let report = symmetric_word_triples::verify::verify_file(
+        output,
+        dictionary,
+        options.parse_or("grid", 3)?,
+        options.parse_or("chunk", 3)?,
+        options.flag("rerun"),
+    )?;
+
+    for (line_number, line, reason) in &report.invalid {
+        println!("Invalid line {line_number}: {line}: {reason}");
+    }
+    for (line_number, line) in &report.duplicates {
+        println!("Duplicate line {line_number}: {line}");
+    }
+    for line in report.missing.iter().flatten() {
+        println!("Missing: {line}");
+    }
+    println!(
+        "{} lines: {} valid, {} invalid, {} duplicates{}",
+        report.lines,
+        report.valid,
+        report.invalid.len(),
+        report.duplicates.len(),
+        match &report.missing {
+            Some(missing) => format!(", {} missing", missing.len()),
+            None => String::new(),
+        }
+    );
+    if !report.is_ok() {
+        return Err(format!("{} doesn't verify.", output.display()).into());
+    }
+    Ok(())
+}
+
+/// `graph --dict path [--grid n] [--chunk n] [--format dot|graphml|csv] [--out file|dir]`
+/// Writes the co-occurrence graph of all solutions, to stdout without `--out`. Into a
+/// directory it is written as `{name}_grid{g}_chunk{c}.{extension of the format}`.
+fn graph(options: &Options) -> Result<(), Box<dyn std::error::Error>> {
+    use symmetric_word_triples::graph::GraphFormat;
+    use symmetric_word_triples::parser::{memo::MemoStrategy, wordfilter::PrefixMap};
+
+    let path = Path::new(options.get("dict").ok_or("--dict path is needed.")?);
+    let grid_size = options.parse_or("grid", 3)?;
+    let chunk_size = options.parse_or("chunk", 3)?;
+    let format = options.parse_or("format", GraphFormat::Dot)?;
+
+    let mut word_dictionary = vec![];
+    parser::file_vec(path, &mut word_dictionary)?;
+    parser::len_filter(&mut word_dictionary, grid_size * chunk_size);
+    let prefix_map = PrefixMap::new(
+        &word_dictionary,
+        grid_size,
+        chunk_size,
+        MemoStrategy::for_grid_size(grid_size),
+    );
+    let graph = prefix_map.cooccurrence_graph();
+    let text = graph.write(format);
+    match options.get("out").map(PathBuf::from) {
+        Some(mut output) => {
+            if output.is_dir() {
+                let name = path
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().replace(' ', "_"))
+                    .unwrap_or_default();
+                output.push(format!(
+                    "{name}_grid{grid_size}_chunk{chunk_size}.{}",
+                    format.extension()
+                ));
+            }
+            std::fs::write(&output, text)?;
+            println!(
+                "{} words, {} edges -> {}",
+                graph.nodes.len(),
+                graph.edges.len(),
+                output.display()
+            );
+        }
+        None => print!("{text}"),
+    }
+    Ok(())
+}
+
+/// `crossword --dict path --shape file [--chunk n] [--limit n] [--render style]`
+/// Fills a symmetric grid with blocked cells. The shape file has a line per row, with `.`
+/// for open and `#` for blocked cells.
+fn crossword(options: &Options) -> Result<(), Box<dyn std::error::Error>> {
+    use symmetric_word_triples::parser::{
+        crossword::CrosswordMap, matrix::GridShape, wordfilter::SearchMode,
+    };
+
+    let path = Path::new(options.get("dict").ok_or("--dict path is needed.")?);
+    let shape_path = options.get("shape").ok_or("--shape file is needed.")?;
+    let shape = GridShape::parse(&std::fs::read_to_string(shape_path)?)?;
+    let chunk_size = options.parse_or("chunk", 3)?;
+    if chunk_size == 0 {
+        return Err("--chunk has to be at least 1.".into());
+    }
+    let mode = match options.get("limit") {
+        Some(_) => SearchMode::First {
+            limit: options.parse_or("limit", 0)?,
+        },
+        None => SearchMode::All,
+    };
+    let render = options.parse_or("render", RenderStyle::Line)?;
+
+    let mut word_dictionary = vec![];
+    parser::file_vec(path, &mut word_dictionary)?;
+    let crossword_map = CrosswordMap::new(&word_dictionary, shape, chunk_size)?;
+    let mut solutions = crossword_map
+        .solutions(mode)
+        .into_iter()
+        .map(|solution| (crossword_map.stringify_rows(&solution), solution))
+        .collect::<Vec<_>>();
+    solutions.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+    print!(
+        "{}",
+        render.render_blocked_document(
+            solutions.iter().map(|(_, solution)| solution),
+            crossword_map.tokens()
+        )
+    );
+    eprintln!(
+        "{} solutions with grid size {} and chunk size {chunk_size}",
+        solutions.len(),
+        crossword_map.shape().size()
+    );
+    Ok(())
+}
+
+/// The `--key value` options and `--flag`s of a command.
+struct Options {
+    values: Vec<(String, String)>,
+    flags: Vec<String>,
+}
+
+impl Options {
+    /// An argument starting with `--` is a flag unless it is followed by a value.
+    fn parse(args: &[String]) -> Result<Options, String> {
+        let mut options = Options {
+            values: vec![],
+            flags: vec![],
+        };
+        let mut args = args.iter().peekable();
+        while let Some(arg) = args.next() {
+            let key = arg
+                .strip_prefix("--")
+                .ok_or_else(|| format!("Unexpected argument \"{arg}\"."))?;
+            match args.next_if(|value| !value.starts_with("--")) {
+                Some(value) => options.values.push((key.to_string(), value.clone())),
+                None => options.flags.push(key.to_string()),
+            }
+        }
+        Ok(options)
+    }
+
+    /// Returns the last value given for the key.
+    fn get(&self, key: &str) -> Option<&str> {
+        self.values
+            .iter()
+            .rev()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Returns every value given for the key, in order.
+    fn get_all(&self, key: &str) -> Vec<&str> {
+        self.values
+            .iter()
+            .filter(|(k, _)| k == key)
+            .map(|(_, value)| value.as_str())
+            .collect()
+    }
+
+    /// Parses the value of the key, or returns the default if the key wasn't given.
+    fn parse_or<T: FromStr>(&self, key: &str, default: T) -> Result<T, String> {
+        match self.get(key) {
+            Some(value) => value
+                .parse()
+                .map_err(|_| format!("Invalid value \"{value}\" for --{key}.")),
+            None => Ok(default),
+        }
+    }
+
+    /// Parses a range given as `a-b`, or a single value `n` for `n-n`.
+    fn range_or(&self, key: &str, default: (usize, usize)) -> Result<(usize, usize), String> {
+        let Some(value) = self.get(key) else {
+            return Ok(default);
+        };
+        let (start, end) = value.split_once('-').unwrap_or((value, value));
+        match (start.parse(), end.parse()) {
+            (Ok(start), Ok(end)) if start <= end => Ok((start, end)),
+            _ => Err(format!("Invalid range \"{value}\" for --{key}.")),
+        }
+    }
+
+    fn flag(&self, key: &str) -> bool {
+        self.flags.iter().any(|flag| flag == key)
+    }
+}
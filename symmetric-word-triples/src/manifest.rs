@@ -0,0 +1,84 @@
+use crate::render::RenderStyle;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Version of the search engine recorded with every output.
+/// Outputs of another version are recomputed by incremental runs, so this has to be bumped
+/// whenever a change alters what an output contains: the solutions found, their order or
+/// how they are written. It is independent of the crate version.
+pub const ENGINE_VERSION: &str = "3";
+
+const MANIFEST_FILE: &str = "manifest.json";
+
+/// What a directory run does with outputs that already exist.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BatchOptions {
+    /// Recompute every combination, even if its output is up to date.
+    pub force: bool,
+    /// Delete the outputs in the manifest that the run doesn't produce anymore,
+    /// because their dictionary is gone or their combination is out of range.
+    pub clean: bool,
+    /// How the solutions are written. Outputs of every style have their own file names.
+    pub render: RenderStyle,
+    /// Where the prefix map indexes are cached between runs. Without it every run builds
+    /// the prefix maps again and nothing but outputs goes into the output directory.
+    pub index_dir: Option<PathBuf>,
+}
+
+/// How an output of a directory run was produced.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    pub input: PathBuf,
+    /// `dictionary_checksum` of the input file.
+    pub input_checksum: u64,
+    pub grid_size: usize,
+    pub chunk_size: usize,
+    pub engine_version: String,
+    /// Number of solutions. No output file is written if there are none.
+    pub solutions: usize,
+}
+
+/// The outputs of a directory run, stored as `manifest.json` in the output directory.
+/// The keys are the output files relative to the output directory.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Manifest {
+    pub outputs: BTreeMap<String, ManifestEntry>,
+}
+
+impl Manifest {
+    /// Reads the manifest of an output directory. A missing manifest is an empty one.
+    pub fn load(output_dir: &Path) -> io::Result<Manifest> {
+        let path = output_dir.join(MANIFEST_FILE);
+        match std::fs::read_to_string(&path) {
+            Ok(json) => serde_json::from_str(&json).map_err(|error| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{}: {error}. Delete it to start over.", path.display()),
+                )
+            }),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(Manifest::default()),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Writes the manifest into the output directory, replacing the old one at once.
+    pub fn save(&self, output_dir: &Path) -> io::Result<()> {
+        let path = output_dir.join(MANIFEST_FILE);
+        let tmp_path = path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&tmp_path, &path)
+    }
+
+    /// Checks if the output was produced from the same input with the same parameters
+    /// and engine version, and is still there.
+    pub fn is_up_to_date(&self, output: &str, entry: &ManifestEntry, output_dir: &Path) -> bool {
+        self.outputs.get(output).is_some_and(|recorded| {
+            recorded.input_checksum == entry.input_checksum
+                && (recorded.grid_size, recorded.chunk_size) == (entry.grid_size, entry.chunk_size)
+                && recorded.engine_version == entry.engine_version
+                && (recorded.solutions == 0 || output_dir.join(output).exists())
+        })
+    }
+}
@@ -1,20 +1,29 @@
+pub mod ascii;
+pub mod crossword;
+pub mod index;
 pub mod matrix;
+pub mod memo;
+mod prune;
 pub mod token;
 pub mod wordfilter;
 
 use self::{
     matrix::TokenMatrix,
-    token::TokenWord,
+    token::{TokenWord, Tokens},
     wordfilter::{ChunkyWord, ChunkyWordDict, WordDict},
 };
+#[cfg(feature = "native")]
 use encoding_rs::WINDOWS_1252;
+#[cfg(feature = "native")]
 use encoding_rs_io::DecodeReaderBytesBuilder;
+#[cfg(feature = "native")]
 use std::{
     fs::File,
     io::{BufRead, BufReader},
     path::Path,
 };
 
+#[cfg(feature = "native")]
 #[inline]
 pub fn file_vec(file_path: &Path, s: &mut WordDict) -> std::io::Result<()> {
     let file = File::open(file_path)?;
@@ -42,6 +51,28 @@ pub fn chunkify_dict(
         .collect()
 }
 
+/// Chunks and tokenizes the words with `grid_size` chunks, in dictionary order. ASCII
+/// dictionaries take the byte-level path of `ascii::tokenize_dict`.
+pub fn tokenize_dict(
+    word_dictionary: &WordDict,
+    grid_size: usize,
+    chunk_size: usize,
+    tokens: &mut Tokens,
+) -> Vec<TokenWord> {
+    if let Some(tkn_words) = ascii::tokenize_dict(word_dictionary, grid_size, chunk_size, tokens) {
+        return tkn_words;
+    }
+    chunkify_dict(word_dictionary, grid_size, chunk_size)
+        .into_iter()
+        .map(|chunky| {
+            chunky
+                .into_iter()
+                .map(|chunk| tokens.insert(chunk))
+                .collect()
+        })
+        .collect()
+}
+
 #[inline]
 pub fn chunkify(word: &str, chunk_size: usize) -> ChunkyWord {
     let mut chunked_word = ChunkyWord::new();
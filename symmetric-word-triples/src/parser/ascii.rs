@@ -0,0 +1,96 @@
+//! Byte-level tokenizing for ASCII dictionaries. A chunk of up to eight ASCII characters
+//! packs into a `u64`, so chunks are looked up by their bytes instead of allocating and
+//! interning a `String` per chunk. The string table is built once the words are tokenized,
+//! with a string per distinct chunk.
+
+use super::token::{TknSize, Token, TokenWord, Tokens};
+use super::wordfilter::{Hr, WordDict};
+use std::collections::HashMap;
+
+/// Longest chunk that packs into a `u64`.
+pub const MAX_CHUNK_SIZE: usize = 8;
+
+/// Packs a chunk of at most `MAX_CHUNK_SIZE` ASCII bytes, seven bits per byte, with the
+/// length in the top byte, so chunks of different lengths never get the same number.
+#[inline]
+pub fn pack(chunk: &[u8]) -> u64 {
+    debug_assert!(chunk.len() <= MAX_CHUNK_SIZE && chunk.is_ascii());
+    chunk
+        .iter()
+        .enumerate()
+        .fold((chunk.len() as u64) << 56, |packed, (i, &byte)| {
+            packed | (byte as u64) << (7 * i)
+        })
+}
+
+/// Checks if `tokenize_dict` can take the words apart byte by byte.
+pub fn fits(dict: &WordDict, chunk_size: usize) -> bool {
+    (1..=MAX_CHUNK_SIZE).contains(&chunk_size) && dict.iter().all(|word| word.is_ascii())
+}
+
+/// The words of a dictionary tokenized by `pack_dict`, before any chunk is a string.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PackedDict {
+    /// The packed chunk of every token, in token order.
+    pub chunks: Vec<u64>,
+    pub words: Vec<TokenWord>,
+}
+
+/// Unpacks a chunk packed by `pack`.
+pub fn unpack(packed: u64) -> String {
+    let len = (packed >> 56) as usize;
+    (0..len)
+        .map(|i| ((packed >> (7 * i)) & 0x7f) as u8 as char)
+        .collect()
+}
+
+/// Tokenizes the words with `grid_size` chunks by their packed bytes only. Tokens are
+/// numbered in the order their chunks first appear. Returns `None` if the dictionary
+/// doesn't `fit` or has more chunks than there are tokens.
+pub fn pack_dict(dict: &WordDict, grid_size: usize, chunk_size: usize) -> Option<PackedDict> {
+    if !fits(dict, chunk_size) {
+        return None;
+    }
+    let mut packed_tokens = HashMap::<u64, Token, Hr>::default();
+    let mut chunks = vec![];
+    let mut words = vec![];
+    for bytes in dict
+        .iter()
+        .map(String::as_bytes)
+        .filter(|bytes| bytes.len().div_ceil(chunk_size) == grid_size)
+    {
+        let mut tkn_word = TokenWord::with_capacity(grid_size);
+        for packed in bytes.chunks(chunk_size).map(pack) {
+            let tkn = match packed_tokens.get(&packed) {
+                Some(&tkn) => tkn,
+                None => {
+                    let tkn = Token(TknSize::try_from(chunks.len()).ok()?);
+                    packed_tokens.insert(packed, tkn);
+                    chunks.push(packed);
+                    tkn
+                }
+            };
+            tkn_word.push(tkn);
+        }
+        words.push(tkn_word);
+    }
+    Some(PackedDict { chunks, words })
+}
+
+/// Does the same as `parser::chunkify_dict` followed by `Tokens::insert` for every chunk,
+/// and gives the same tokens in the same order. The words are tokenized by `pack_dict` and
+/// `tokens` gets the strings of the distinct chunks at the end. Returns `None` if the
+/// dictionary doesn't `fit` or `tokens` isn't empty.
+pub fn tokenize_dict(
+    dict: &WordDict,
+    grid_size: usize,
+    chunk_size: usize,
+    tokens: &mut Tokens,
+) -> Option<Vec<TokenWord>> {
+    if !tokens.is_empty() {
+        return None;
+    }
+    let packed = pack_dict(dict, grid_size, chunk_size)?;
+    tokens.extend(packed.chunks.into_iter().map(unpack));
+    Some(packed.words)
+}
@@ -0,0 +1,255 @@
+//! Symmetric grids with blocked cells, like crosswords. Every maximal run of open cells of a
+//! row is a word of the run's length, and since the grid is symmetric, so is every run of a
+//! column.
+
+use super::matrix::{BlockedTokenMatrix, GridShape};
+use super::token::{Token, TokenWord, Tokens};
+use super::wordfilter::{SearchMode, WordDict};
+use crate::parser;
+use radix_trie::{Trie, TrieCommon};
+use rand::seq::SliceRandom;
+use rand_chacha::ChaCha8Rng;
+use std::collections::BTreeMap;
+use std::ops::{ControlFlow, Range};
+
+/// The words of a dictionary with a prefix index per word length, for the run lengths
+/// of a grid shape.
+pub struct CrosswordMap {
+    tokens: Tokens,
+    /// Words by their number of chunks.
+    words: BTreeMap<usize, Trie<TokenWord, ()>>,
+    shape: GridShape,
+    chunk_size: usize,
+}
+
+impl CrosswordMap {
+    /// Chunks and tokenizes the words whose number of chunks is the length of a run of the
+    /// shape. Other words are left out, so the dictionary doesn't have to be filtered first.
+    /// Fails if `chunk_size` is 0.
+    pub fn new(
+        dict: &WordDict,
+        shape: GridShape,
+        chunk_size: usize,
+    ) -> Result<CrosswordMap, String> {
+        if chunk_size == 0 {
+            return Err("The chunk size has to be at least 1.".to_string());
+        }
+        let lengths = shape.run_lengths();
+        let mut tokens = Tokens::new();
+        let mut words = BTreeMap::new();
+        for word in dict {
+            if !word.len().is_multiple_of(chunk_size)
+                || !lengths.contains(&(word.len() / chunk_size))
+            {
+                continue;
+            }
+            let tkn_word = parser::chunkify(word, chunk_size)
+                .into_iter()
+                .map(|chunk| tokens.insert(chunk))
+                .collect::<TokenWord>();
+            words
+                .entry(tkn_word.0.len())
+                .or_insert_with(Trie::new)
+                .insert(tkn_word, ());
+        }
+        Ok(CrosswordMap {
+            tokens,
+            words,
+            shape,
+            chunk_size,
+        })
+    }
+
+    #[inline]
+    pub fn shape(&self) -> &GridShape {
+        &self.shape
+    }
+
+    #[inline]
+    pub fn tokens(&self) -> &Tokens {
+        &self.tokens
+    }
+
+    #[inline]
+    pub fn chunk_size(&self) -> usize {
+        self.chunk_size
+    }
+
+    /// Number of words with `length` chunks.
+    pub fn word_count(&self, length: usize) -> usize {
+        self.words.get(&length).map_or(0, |trie| trie.len())
+    }
+
+    /// Collects the grids that `mode` asks for.
+    pub fn solutions(&self, mode: SearchMode) -> Vec<BlockedTokenMatrix> {
+        let limit = mode.limit();
+        let mut solutions = vec![];
+        if self.shape.size() == 0 || limit == 0 {
+            return solutions;
+        }
+        let _ = self.next_row(
+            &mut BlockedTokenMatrix::new(self.shape.clone()),
+            &mut mode.rng(),
+            &mut |solution| {
+                solutions.push(solution.clone());
+                if solutions.len() >= limit {
+                    ControlFlow::Break(())
+                } else {
+                    ControlFlow::Continue(())
+                }
+            },
+        );
+        solutions
+    }
+
+    /// Counts the grids without storing them.
+    pub fn count(&self) -> usize {
+        let mut count = 0;
+        if self.shape.size() > 0 {
+            let _ = self.next_row(
+                &mut BlockedTokenMatrix::new(self.shape.clone()),
+                &mut None,
+                &mut |_| {
+                    count += 1;
+                    ControlFlow::Continue(())
+                },
+            );
+        }
+        count
+    }
+
+    /// Every row as a string, with `#` for the characters of the blocked cells.
+    pub fn stringify_rows(&self, matrix: &BlockedTokenMatrix) -> Vec<String> {
+        matrix
+            .rows()
+            .map(|row| {
+                row.iter()
+                    .map(|cell| match cell {
+                        Some(tkn) => self.tokens.stringify_token(*tkn).unwrap_or_default(),
+                        None => "#".repeat(self.chunk_size),
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Fills the next row. Its cells left of the diagonal are the cells of the filled rows
+    /// in its column.
+    fn next_row<F>(
+        &self,
+        matrix: &mut BlockedTokenMatrix,
+        rng: &mut Option<ChaCha8Rng>,
+        on_solution: &mut F,
+    ) -> ControlFlow<()>
+    where
+        F: FnMut(&BlockedTokenMatrix) -> ControlFlow<()>,
+    {
+        if matrix.is_full() {
+            return on_solution(matrix);
+        }
+        let depth = matrix.len();
+        let mut cells = (0..self.shape.size())
+            .map(|col| {
+                if col < depth {
+                    matrix.get(col, depth)
+                } else {
+                    None
+                }
+            })
+            .collect::<Vec<_>>();
+        let runs = self.shape.runs(depth);
+        self.fill_runs(matrix, &mut cells, &runs, rng, on_solution)
+    }
+
+    /// Fills the first of `runs` with every word that fits its fixed cells, then the others.
+    /// Once every run is filled, the row is pushed and the next row is filled.
+    fn fill_runs<F>(
+        &self,
+        matrix: &mut BlockedTokenMatrix,
+        cells: &mut Vec<Option<Token>>,
+        runs: &[Range<usize>],
+        rng: &mut Option<ChaCha8Rng>,
+        on_solution: &mut F,
+    ) -> ControlFlow<()>
+    where
+        F: FnMut(&BlockedTokenMatrix) -> ControlFlow<()>,
+    {
+        let Some((run, rest)) = runs.split_first() else {
+            matrix.push(cells.clone()).unwrap();
+            let flow = if self.later_rows_fit(matrix) {
+                self.next_row(matrix, rng, on_solution)
+            } else {
+                ControlFlow::Continue(())
+            };
+            matrix.pop();
+            return flow;
+        };
+        // The cells of the run left of the diagonal are fixed by the filled rows.
+        let fixed_end = matrix.len().clamp(run.start, run.end);
+        let prefix = cells[run.start..fixed_end]
+            .iter()
+            .map(|cell| cell.expect("Open cells of filled rows have tokens."))
+            .collect::<TokenWord>();
+        if fixed_end == run.end {
+            if !self.contains(run.len(), &prefix) {
+                return ControlFlow::Continue(());
+            }
+            return self.fill_runs(matrix, cells, rest, rng, on_solution);
+        }
+
+        let mut candidates = self.prefix_words(run.len(), &prefix);
+        if let Some(rng) = rng {
+            candidates.shuffle(rng);
+        }
+        for word in candidates {
+            for (col, tkn) in (fixed_end..run.end).zip(&word.0[fixed_end - run.start..]) {
+                cells[col] = Some(*tkn);
+            }
+            self.fill_runs(matrix, cells, rest, rng, on_solution)?;
+        }
+        ControlFlow::Continue(())
+    }
+
+    /// Checks that the runs of the rows that aren't filled yet can still be words, given the
+    /// cells the filled rows fix in their columns.
+    fn later_rows_fit(&self, matrix: &BlockedTokenMatrix) -> bool {
+        let filled = matrix.len();
+        (filled..self.shape.size()).all(|row| {
+            self.shape
+                .runs(row)
+                .into_iter()
+                .filter(|run| run.start < filled)
+                .all(|run| {
+                    let fixed_end = run.end.min(filled);
+                    let prefix = (run.start..fixed_end)
+                        .map(|col| matrix.get(col, row).expect("Open cells have tokens."))
+                        .collect::<TokenWord>();
+                    if fixed_end == run.end {
+                        self.contains(run.len(), &prefix)
+                    } else {
+                        self.has_prefix(run.len(), &prefix)
+                    }
+                })
+        })
+    }
+
+    fn contains(&self, length: usize, word: &TokenWord) -> bool {
+        self.words
+            .get(&length)
+            .is_some_and(|trie| trie.get(word).is_some())
+    }
+
+    fn has_prefix(&self, length: usize, prefix: &TokenWord) -> bool {
+        self.words
+            .get(&length)
+            .is_some_and(|trie| trie.get_raw_descendant(prefix).is_some())
+    }
+
+    fn prefix_words(&self, length: usize, prefix: &TokenWord) -> Vec<TokenWord> {
+        self.words
+            .get(&length)
+            .and_then(|trie| trie.get_raw_descendant(prefix))
+            .map(|subtrie| subtrie.keys().cloned().collect())
+            .unwrap_or_default()
+    }
+}
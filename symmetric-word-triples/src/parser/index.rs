@@ -0,0 +1,403 @@
+//! Binary index format of a built `PrefixMap`.
+//!
+//! Layout, all integers little endian:
+//! - magic `SWTI` and the format version (u32),
+//! - grid size, chunk size (u64) and the checksum of the source dictionary (u64),
+//! - the number of tokens (u32), then every token string in token order as length (u32) and bytes,
+//! - the number of words (u64), then every word as `grid size` tokens (u16) in ascending order,
+//! - the number of pruned words (u64), then the pruned words the same way.
+//!
+//! The words are fixed size records in sorted order, so `PrefixMap::map_index` can binary
+//! search them in a memory-mapped file instead of reading them into a trie. The pruned words
+//! are kept apart, so a loaded or mapped index searches the same words as the prefix map
+//! it was written from.
+
+use super::memo::MemoStrategy;
+use super::token::{TknSize, Token, TokenWord, Tokens};
+#[cfg(feature = "native")]
+use super::wordfilter::Hr;
+use super::wordfilter::{PrefixMap, WordDict};
+#[cfg(feature = "native")]
+use memmap2::Mmap;
+use std::io::{self, Read, Write};
+use std::sync::Arc;
+#[cfg(feature = "native")]
+use std::{cmp::Ordering, collections::HashSet, fs::File, io::Cursor, path::Path};
+
+const MAGIC: &[u8; 4] = b"SWTI";
+
+/// Version of the index format. Indexes with another version are rejected.
+pub const INDEX_VERSION: u32 = 2;
+
+/// Longest token string an index may contain, in bytes. Guards against corrupt lengths.
+const MAX_TOKEN_LEN: u32 = 4096;
+
+/// The build parameters stored at the start of every index.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IndexHeader {
+    pub version: u32,
+    pub grid_size: usize,
+    pub chunk_size: usize,
+    /// `dictionary_checksum` of the dictionary the index was built from.
+    pub source_checksum: u64,
+}
+
+impl IndexHeader {
+    /// The header of an index in the current format.
+    pub fn new(grid_size: usize, chunk_size: usize, source_checksum: u64) -> IndexHeader {
+        IndexHeader {
+            version: INDEX_VERSION,
+            grid_size,
+            chunk_size,
+            source_checksum,
+        }
+    }
+
+    /// Checks that an index with this header can be used in place of `expected`.
+    pub fn check(&self, expected: &IndexHeader) -> io::Result<()> {
+        self.check_version(expected.version)?;
+        if (self.grid_size, self.chunk_size) != (expected.grid_size, expected.chunk_size) {
+            return Err(invalid_data(format!(
+                "The index was built for grid size {} and chunk size {}, not {} and {}.",
+                self.grid_size, self.chunk_size, expected.grid_size, expected.chunk_size
+            )));
+        }
+        if self.source_checksum != expected.source_checksum {
+            return Err(invalid_data(
+                "The index is stale, the dictionary changed since it was built.".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn check_version(&self, version: u32) -> io::Result<()> {
+        if self.version != version {
+            return Err(invalid_data(format!(
+                "Index format version {} is not supported, expected {version}.",
+                self.version
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// A checksum of the words of a dictionary (64 bit FNV-1a), stable across runs and platforms.
+pub fn dictionary_checksum(word_dictionary: &WordDict) -> u64 {
+    fnv1a(
+        word_dictionary
+            .iter()
+            .flat_map(|word| word.bytes().chain([b'\n'])),
+    )
+}
+
+/// 64 bit FNV-1a hash of some bytes.
+pub(crate) fn fnv1a<I: IntoIterator<Item = u8>>(bytes: I) -> u64 {
+    let mut hash = 0xcbf2_9ce4_8422_2325u64;
+    for byte in bytes {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
+
+/// Reads only the header of an index.
+pub fn read_index_header<R: Read>(reader: &mut R) -> io::Result<IndexHeader> {
+    let mut magic = [0; 4];
+    reader.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(invalid_data("Not a prefix map index.".to_string()));
+    }
+    let header = IndexHeader {
+        version: read_u32(reader)?,
+        grid_size: read_u64(reader)? as usize,
+        chunk_size: read_u64(reader)? as usize,
+        source_checksum: read_u64(reader)?,
+    };
+    if header.grid_size == 0 || header.chunk_size == 0 {
+        return Err(invalid_data(
+            "The grid and chunk size of an index have to be at least 1.".to_string(),
+        ));
+    }
+    Ok(header)
+}
+
+impl PrefixMap {
+    /// Writes the tokens and words of the prefix map as an index.
+    /// `source_checksum` is the `dictionary_checksum` of the dictionary it was built from.
+    pub fn write_index<W: Write>(&self, mut writer: W, source_checksum: u64) -> io::Result<()> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&INDEX_VERSION.to_le_bytes())?;
+        writer.write_all(&(self.grid_size() as u64).to_le_bytes())?;
+        writer.write_all(&(self.chunk_size() as u64).to_le_bytes())?;
+        writer.write_all(&source_checksum.to_le_bytes())?;
+
+        let entries = self.tokens().entries();
+        writer.write_all(&(entries.len() as u32).to_le_bytes())?;
+        for (_, chunk) in entries {
+            writer.write_all(&(chunk.len() as u32).to_le_bytes())?;
+            writer.write_all(chunk.as_bytes())?;
+        }
+
+        let words = self.get_prefix_words(&TokenWord::new());
+        write_words(&mut writer, words.iter().map(Arc::as_ref))?;
+        // The pruned words too, so the index holds the whole dictionary.
+        write_words(&mut writer, self.pruned_token_words())?;
+        writer.flush()
+    }
+
+    /// Reads an index written by `write_index` and rejects it unless its header matches
+    /// `expected`, so an index of an older format or of a changed dictionary is never used.
+    pub fn read_index<R: Read>(
+        mut reader: R,
+        expected: &IndexHeader,
+        memo: MemoStrategy,
+    ) -> io::Result<PrefixMap> {
+        let header = read_index_header(&mut reader)?;
+        header.check(expected)?;
+
+        let tokens = read_tokens(&mut reader)?;
+        let token_dict = read_words(&mut reader, header.grid_size, &tokens)?;
+        let pruned = read_words(&mut reader, header.grid_size, &tokens)?;
+
+        Ok(PrefixMap::from_pruned_words(
+            tokens,
+            token_dict,
+            pruned.into_iter().collect(),
+            header.grid_size,
+            header.chunk_size,
+            memo,
+        ))
+    }
+}
+
+/// The words section of a memory-mapped index.
+/// Lookups binary search the records in place and only copy the words they return.
+#[cfg(feature = "native")]
+pub(crate) struct MappedWords {
+    mmap: Mmap,
+    offset: usize,
+    count: usize,
+    grid_size: usize,
+}
+
+#[cfg(feature = "native")]
+impl MappedWords {
+    #[inline]
+    fn token(&self, word: usize, position: usize) -> TknSize {
+        let start = self.offset + (word * self.grid_size + position) * 2;
+        TknSize::from_le_bytes([self.mmap[start], self.mmap[start + 1]])
+    }
+
+    /// Compares the start of the word at `index` with `prefix`.
+    fn cmp_prefix(&self, index: usize, prefix: &TokenWord) -> Ordering {
+        prefix
+            .0
+            .iter()
+            .enumerate()
+            .map(|(position, tkn)| self.token(index, position).cmp(&tkn.0))
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(Ordering::Equal)
+    }
+
+    /// The first index in `0..count` for which `pred` is false, `pred` being true before it.
+    fn partition_point<P: Fn(usize) -> bool>(&self, pred: P) -> usize {
+        let (mut low, mut high) = (0, self.count);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            if pred(mid) {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+        low
+    }
+
+    pub(crate) fn prefix_words(&self, prefix: &TokenWord) -> Vec<Arc<TokenWord>> {
+        if prefix.0.len() > self.grid_size {
+            return vec![];
+        }
+        let begin = self.partition_point(|i| self.cmp_prefix(i, prefix).is_lt());
+        let end = self.partition_point(|i| self.cmp_prefix(i, prefix).is_le());
+        (begin..end)
+            .map(|i| {
+                let tkn_word = (0..self.grid_size)
+                    .map(|position| Token(self.token(i, position)))
+                    .collect();
+                Arc::new(tkn_word)
+            })
+            .collect()
+    }
+
+    pub(crate) fn contains(&self, word: &TokenWord) -> bool {
+        word.0.len() == self.grid_size && !self.prefix_words(word).is_empty()
+    }
+
+    /// Checks that every token is one of the `token_count` tokens and that the words are in
+    /// strictly ascending order, as the binary search needs.
+    fn check(&self, token_count: TknSize) -> io::Result<()> {
+        for i in 0..self.count {
+            for position in 0..self.grid_size {
+                let tkn = self.token(i, position);
+                if tkn >= token_count {
+                    return Err(invalid_data(format!("Unknown token {tkn}.")));
+                }
+            }
+            let ascending = i == 0
+                || (0..self.grid_size)
+                    .map(|position| self.token(i - 1, position).cmp(&self.token(i, position)))
+                    .find(|ordering| ordering.is_ne())
+                    .is_some_and(Ordering::is_lt);
+            if !ascending {
+                return Err(invalid_data(
+                    "The words of the index are not in ascending order.".to_string(),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "native")]
+impl PrefixMap {
+    /// Memory-maps an index written by `write_index`. Only the header and the tokens are read,
+    /// the words are searched in the mapped file, so processes mapping the same index share
+    /// its pages. The caller checks the returned header against the dictionary it expects.
+    pub fn map_index(path: &Path, memo: MemoStrategy) -> io::Result<(IndexHeader, PrefixMap)> {
+        let file = File::open(path)?;
+        // SAFETY: Indexes are written to a temporary file and renamed into place, so a mapped
+        // index file is never changed, only replaced.
+        let mmap = unsafe { Mmap::map(&file)? };
+
+        let mut reader = Cursor::new(&mmap[..]);
+        let header = read_index_header(&mut reader)?;
+        header.check_version(INDEX_VERSION)?;
+        let tokens = read_tokens(&mut reader)?;
+        let count = read_u64(&mut reader)? as usize;
+        let offset = reader.position() as usize;
+        let end = header
+            .grid_size
+            .checked_mul(2)
+            .and_then(|record| record.checked_mul(count))
+            .and_then(|size| size.checked_add(offset))
+            .filter(|&end| end <= mmap.len())
+            .ok_or_else(|| {
+                invalid_data("The index has the wrong size for its words.".to_string())
+            })?;
+        reader.set_position(end as u64);
+        let pruned = read_words(&mut reader, header.grid_size, &tokens)?
+            .into_iter()
+            .collect::<HashSet<_, Hr>>();
+        if reader.position() as usize != mmap.len() {
+            return Err(invalid_data(
+                "The index has the wrong size for its words.".to_string(),
+            ));
+        }
+
+        let words = MappedWords {
+            mmap,
+            offset,
+            count,
+            grid_size: header.grid_size,
+        };
+        words.check(tokens.len())?;
+        let prefix_map = PrefixMap::from_mapped_words(
+            tokens,
+            words,
+            pruned,
+            header.grid_size,
+            header.chunk_size,
+            memo,
+        );
+        Ok((header, prefix_map))
+    }
+}
+
+fn read_tokens<R: Read>(reader: &mut R) -> io::Result<Tokens> {
+    let mut tokens = Tokens::new();
+    let token_count = read_u32(reader)?;
+    if token_count > TknSize::MAX as u32 {
+        return Err(invalid_data(format!("Too many tokens: {token_count}.")));
+    }
+    for i in 0..token_count {
+        let len = read_u32(reader)?;
+        if len > MAX_TOKEN_LEN {
+            return Err(invalid_data(format!(
+                "A token is {len} bytes long, at most {MAX_TOKEN_LEN} are allowed."
+            )));
+        }
+        // Grows with the bytes that are actually there instead of trusting the length.
+        let mut chunk = vec![];
+        reader.take(len as u64).read_to_end(&mut chunk)?;
+        if chunk.len() != len as usize {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "The index ends inside a token.",
+            ));
+        }
+        let chunk = String::from_utf8(chunk)
+            .map_err(|_| invalid_data("A token is not valid UTF-8.".to_string()))?;
+        if tokens.insert(chunk).0 as u32 != i {
+            return Err(invalid_data("The index has duplicate tokens.".to_string()));
+        }
+    }
+    Ok(tokens)
+}
+
+/// Writes the number of words and the words in ascending token order.
+fn write_words<'a, W, I>(writer: &mut W, words: I) -> io::Result<()>
+where
+    W: Write,
+    I: IntoIterator<Item = &'a TokenWord>,
+{
+    let mut words = words.into_iter().collect::<Vec<_>>();
+    words.sort_unstable_by(|a, b| a.0.iter().map(|tkn| tkn.0).cmp(b.0.iter().map(|tkn| tkn.0)));
+    writer.write_all(&(words.len() as u64).to_le_bytes())?;
+    for word in words {
+        for tkn in &word.0 {
+            writer.write_all(&tkn.0.to_le_bytes())?;
+        }
+    }
+    Ok(())
+}
+
+/// Reads a section written by `write_words`, checking that every token is one of `tokens`.
+fn read_words<R: Read>(
+    reader: &mut R,
+    grid_size: usize,
+    tokens: &Tokens,
+) -> io::Result<Vec<TokenWord>> {
+    let word_count = read_u64(reader)?;
+    let mut token_dict = vec![];
+    for _ in 0..word_count {
+        // Not allocated up front, `grid_size` may come from a corrupt header.
+        let mut tkn_word = TokenWord::new();
+        for _ in 0..grid_size {
+            let mut tkn = [0; 2];
+            reader.read_exact(&mut tkn)?;
+            let tkn = Token(TknSize::from_le_bytes(tkn));
+            if tkn.0 >= tokens.len() {
+                return Err(invalid_data(format!("Unknown token {}.", tkn.0)));
+            }
+            tkn_word.push(tkn);
+        }
+        token_dict.push(tkn_word);
+    }
+    Ok(token_dict)
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> io::Result<u32> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_u64<R: Read>(reader: &mut R) -> io::Result<u64> {
+    let mut bytes = [0; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
@@ -1,5 +1,6 @@
 use super::token::{Token, TokenWord};
 use std::fmt::Debug;
+use std::ops::Range;
 
 #[derive(Hash, Clone, Debug, PartialEq, Eq)]
 pub struct TokenMatrix {
@@ -89,6 +90,12 @@ impl TokenMatrix {
         self.matrix.len() / self.capacity()
     }
 
+    /// Checks if the matrix has no filled rows.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.matrix.is_empty()
+    }
+
     /// Checks if the matrix is full.
     #[inline]
     pub fn is_full(&self) -> bool {
@@ -145,3 +152,194 @@ impl TokenMatrix {
         self.matrix.chunks(self.capacity())
     }
 }
+
+/// Which cells of a square grid are blocked, like the black cells of a crossword.
+/// Rows are also columns in a symmetric grid, so the blocked cells have to be symmetric too.
+#[derive(Hash, Clone, Debug, PartialEq, Eq)]
+pub struct GridShape {
+    size: usize,
+    blocked: Vec<bool>,
+}
+
+impl GridShape {
+    /// A shape without blocked cells.
+    pub fn open(size: usize) -> GridShape {
+        GridShape {
+            size,
+            blocked: vec![false; size * size],
+        }
+    }
+
+    /// Parses one line per row, with `.` for an open and `#` for a blocked cell.
+    /// Empty lines are skipped.
+    pub fn parse(s: &str) -> Result<GridShape, String> {
+        let lines = s
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .collect::<Vec<_>>();
+        let size = lines.len();
+        let mut blocked = Vec::with_capacity(size * size);
+        for (i, line) in lines.iter().enumerate() {
+            if line.chars().count() != size {
+                return Err(format!(
+                    "Row {} of the shape has {} cells instead of {size}.",
+                    i + 1,
+                    line.chars().count()
+                ));
+            }
+            for c in line.chars() {
+                match c {
+                    '.' => blocked.push(false),
+                    '#' => blocked.push(true),
+                    _ => {
+                        return Err(format!(
+                            "Unknown cell \"{c}\" in the shape, expected . or #."
+                        ))
+                    }
+                }
+            }
+        }
+        let shape = GridShape { size, blocked };
+        for row in 0..size {
+            for col in 0..row {
+                if shape.is_blocked(row, col) != shape.is_blocked(col, row) {
+                    return Err(format!(
+                        "The shape isn't symmetric at row {}, column {}.",
+                        row + 1,
+                        col + 1
+                    ));
+                }
+            }
+        }
+        Ok(shape)
+    }
+
+    #[inline]
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    #[inline]
+    pub fn is_blocked(&self, row: usize, col: usize) -> bool {
+        self.blocked[row * self.size + col]
+    }
+
+    /// The lengths of all runs, sorted and without repetitions.
+    pub fn run_lengths(&self) -> Vec<usize> {
+        let mut lengths = (0..self.size)
+            .flat_map(|row| self.runs(row))
+            .map(|run| run.len())
+            .collect::<Vec<_>>();
+        lengths.sort_unstable();
+        lengths.dedup();
+        lengths
+    }
+
+    /// The maximal runs of open cells of a row, as column ranges. Because the shape is
+    /// symmetric, these are also the runs of the column with the same index.
+    pub fn runs(&self, row: usize) -> Vec<Range<usize>> {
+        let mut runs = vec![];
+        let mut start = None;
+        for col in 0..=self.size {
+            match (start, col < self.size && !self.is_blocked(row, col)) {
+                (None, true) => start = Some(col),
+                (Some(begin), false) => {
+                    runs.push(begin..col);
+                    start = None;
+                }
+                _ => {}
+            }
+        }
+        runs
+    }
+}
+
+/// A `TokenMatrix` with blocked cells, filled row by row. Blocked cells hold no token.
+#[derive(Hash, Clone, Debug, PartialEq, Eq)]
+pub struct BlockedTokenMatrix {
+    shape: GridShape,
+    matrix: Vec<Option<Token>>,
+}
+
+impl BlockedTokenMatrix {
+    pub fn new(shape: GridShape) -> BlockedTokenMatrix {
+        let capacity = shape.size() * shape.size();
+        BlockedTokenMatrix {
+            shape,
+            matrix: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Pushes a row, which has to have a token exactly in the open cells of the shape.
+    pub fn push(&mut self, row: Vec<Option<Token>>) -> Result<(), String> {
+        let size = self.shape.size();
+        let row_i = self.len();
+        if row.len() != size || row_i == size {
+            return Err(format!(
+                "A row of {} cells doesn't fit into row {row_i} of a grid of size {size}.",
+                row.len()
+            ));
+        }
+        if let Some(col) =
+            (0..size).find(|&col| row[col].is_none() != self.shape.is_blocked(row_i, col))
+        {
+            return Err(format!(
+                "Cell {col} of row {row_i} doesn't match the shape."
+            ));
+        }
+        self.matrix.extend(row);
+        Ok(())
+    }
+
+    /// Pops the last row.
+    pub fn pop(&mut self) -> Option<Vec<Option<Token>>> {
+        let rows = self.len();
+        if rows == 0 {
+            return None;
+        }
+        Some(self.matrix.split_off((rows - 1) * self.shape.size()))
+    }
+
+    /// Returns the token in the cell, `None` if the cell is blocked or not filled yet.
+    #[inline]
+    pub fn get(&self, row: usize, col: usize) -> Option<Token> {
+        self.matrix
+            .get(row * self.shape.size() + col)
+            .copied()
+            .flatten()
+    }
+
+    #[inline]
+    pub fn shape(&self) -> &GridShape {
+        &self.shape
+    }
+
+    /// Get the number of filled rows in the matrix.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.matrix.len() / self.shape.size().max(1)
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.matrix.is_empty()
+    }
+
+    #[inline]
+    pub fn is_full(&self) -> bool {
+        self.len() == self.shape.size()
+    }
+
+    /// Returns an iterator over the filled rows of the matrix.
+    #[inline]
+    pub fn rows(&self) -> impl Iterator<Item = &[Option<Token>]> {
+        self.matrix.chunks(self.shape.size().max(1))
+    }
+
+    /// Checks if the filled cells are symmetric.
+    pub fn is_symmetric(&self) -> bool {
+        let rows = self.len();
+        (0..rows).all(|row| (0..row).all(|col| self.get(row, col) == self.get(col, row)))
+    }
+}
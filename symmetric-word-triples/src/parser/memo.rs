@@ -0,0 +1,192 @@
+//! Memo table of the prefix lookups of a `PrefixMap`.
+
+use super::token::TokenWord;
+use super::wordfilter::Hr;
+use dashmap::DashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Number of prefixes a memo table keeps by default.
+pub const DEFAULT_MEMO_CAPACITY: usize = 1 << 18;
+
+/// Number of lookups after which an `Auto` table checks its hit rate.
+pub const AUTO_WINDOW: u64 = 1 << 16;
+
+/// An `Auto` table turns itself off if less of the lookups of a window hit.
+const AUTO_MIN_HIT_RATE: f64 = 0.25;
+
+/// Number of lookups an `Auto` table stays off before it tries another window.
+pub const AUTO_PAUSE: u64 = 4 * AUTO_WINDOW;
+
+/// How the prefix lookups of the searches of a `PrefixMap` are memoized.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MemoStrategy {
+    /// Every lookup goes to the words.
+    Off,
+    /// Lookups go through a table of at most `capacity` prefixes.
+    /// The least recently used prefixes are evicted when it is full.
+    Bounded { capacity: usize },
+    /// Like `Bounded`, but the table turns itself off when too few lookups of a window hit
+    /// it, as in searches that rarely repeat a prefix. After a pause it tries again with an
+    /// empty table, so a cold start doesn't turn it off for the whole search.
+    Auto { capacity: usize },
+}
+
+impl MemoStrategy {
+    /// The strategy used unless one is given. Grids of two words look up too few prefixes
+    /// for a table to pay off.
+    pub fn for_grid_size(grid_size: usize) -> MemoStrategy {
+        if grid_size > 2 {
+            MemoStrategy::Auto {
+                capacity: DEFAULT_MEMO_CAPACITY,
+            }
+        } else {
+            MemoStrategy::Off
+        }
+    }
+
+    fn capacity(&self) -> usize {
+        match *self {
+            MemoStrategy::Off => 0,
+            MemoStrategy::Bounded { capacity } | MemoStrategy::Auto { capacity } => capacity,
+        }
+    }
+}
+
+struct MemoEntry {
+    words: Vec<Arc<TokenWord>>,
+    /// Clock value of the last lookup of the prefix.
+    last_used: AtomicU64,
+}
+
+/// A bounded table from prefixes to their words, shared by the threads of a search.
+pub(crate) struct MemoTable {
+    strategy: MemoStrategy,
+    entries: DashMap<TokenWord, MemoEntry, Hr>,
+    enabled: AtomicBool,
+    clock: AtomicU64,
+    /// Hits and misses of the current `Auto` window.
+    window_hits: AtomicU64,
+    window_lookups: AtomicU64,
+    /// Lookups that skipped an `Auto` table since it turned itself off.
+    paused_lookups: AtomicU64,
+    /// Held by the thread that evicts, so the others go on instead of evicting too.
+    evicting: Mutex<()>,
+}
+
+impl MemoTable {
+    pub(crate) fn new(strategy: MemoStrategy) -> MemoTable {
+        MemoTable {
+            strategy,
+            entries: DashMap::default(),
+            enabled: AtomicBool::new(strategy.capacity() > 0),
+            clock: AtomicU64::new(0),
+            window_hits: AtomicU64::new(0),
+            window_lookups: AtomicU64::new(0),
+            paused_lookups: AtomicU64::new(0),
+            evicting: Mutex::new(()),
+        }
+    }
+
+    pub(crate) fn strategy(&self) -> MemoStrategy {
+        self.strategy
+    }
+
+    /// False if there is no table or an `Auto` table turned itself off.
+    #[inline]
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    /// Whether a lookup should go through the table. Counts the lookups that skip a paused
+    /// `Auto` table, and turns it on again once the pause is over.
+    #[inline]
+    pub(crate) fn should_lookup(&self) -> bool {
+        if self.is_enabled() {
+            return true;
+        }
+        if !matches!(self.strategy, MemoStrategy::Auto { .. })
+            || self.paused_lookups.fetch_add(1, Ordering::Relaxed) + 1 < AUTO_PAUSE
+        {
+            return false;
+        }
+        self.paused_lookups.store(0, Ordering::Relaxed);
+        self.window_hits.store(0, Ordering::Relaxed);
+        self.window_lookups.store(0, Ordering::Relaxed);
+        self.enabled.store(true, Ordering::Relaxed);
+        true
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// The memoized words of a prefix.
+    #[inline]
+    pub(crate) fn get(&self, prefix: &TokenWord) -> Option<Vec<Arc<TokenWord>>> {
+        let words = self.entries.get(prefix).map(|entry| {
+            let now = self.clock.fetch_add(1, Ordering::Relaxed);
+            entry.last_used.store(now, Ordering::Relaxed);
+            entry.words.clone()
+        });
+        self.observe(words.is_some());
+        words
+    }
+
+    /// Memoizes the words of a prefix that missed, evicting if the table is full.
+    pub(crate) fn insert(&self, prefix: TokenWord, words: Vec<Arc<TokenWord>>) {
+        if !self.is_enabled() {
+            return;
+        }
+        let now = self.clock.fetch_add(1, Ordering::Relaxed);
+        self.entries.insert(
+            prefix,
+            MemoEntry {
+                words,
+                last_used: AtomicU64::new(now),
+            },
+        );
+        if self.entries.len() > self.strategy.capacity() {
+            self.evict();
+        }
+    }
+
+    /// Removes the least recently used quarter of the entries, so evictions are rare.
+    fn evict(&self) {
+        let Ok(_guard) = self.evicting.try_lock() else {
+            return;
+        };
+        let mut last_used = self
+            .entries
+            .iter()
+            .map(|entry| entry.last_used.load(Ordering::Relaxed))
+            .collect::<Vec<_>>();
+        if last_used.is_empty() {
+            return;
+        }
+        let keep = self.strategy.capacity() - self.strategy.capacity() / 4;
+        let evict = last_used.len().saturating_sub(keep).max(1);
+        let (_, &mut cutoff, _) = last_used.select_nth_unstable(evict - 1);
+        self.entries
+            .retain(|_, entry| entry.last_used.load(Ordering::Relaxed) > cutoff);
+    }
+
+    /// Counts a lookup and pauses an `Auto` table at the end of a window with a low hit rate.
+    fn observe(&self, hit: bool) {
+        if !matches!(self.strategy, MemoStrategy::Auto { .. }) {
+            return;
+        }
+        if hit {
+            self.window_hits.fetch_add(1, Ordering::Relaxed);
+        }
+        if self.window_lookups.fetch_add(1, Ordering::Relaxed) + 1 < AUTO_WINDOW {
+            return;
+        }
+        let hits = self.window_hits.swap(0, Ordering::Relaxed);
+        self.window_lookups.store(0, Ordering::Relaxed);
+        if (hits as f64) < AUTO_MIN_HIT_RATE * AUTO_WINDOW as f64 {
+            self.enabled.store(false, Ordering::Relaxed);
+            self.entries.clear();
+        }
+    }
+}
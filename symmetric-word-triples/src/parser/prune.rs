@@ -0,0 +1,87 @@
+//! Arc consistency over the rows of a symmetric square, run on the tokenized dictionary
+//! before searching. Row `i` of a square is also its column `i`, so the chunk at position
+//! `j` of row `i` is the chunk at position `i` of row `j`. A word can only be row `i` if for
+//! every other row `j` some word that can be row `j` has that chunk at position `i`.
+
+use super::token::TokenWord;
+
+/// Returns for every word whether it can be any row of a square. Words can only be removed
+/// from a row once no word of another row supports them, so this never removes a word of a
+/// solution. Words with another number of chunks than `grid_size` are kept.
+pub(crate) fn arc_consistent_words(
+    words: &[&TokenWord],
+    grid_size: usize,
+    token_count: usize,
+) -> Vec<bool> {
+    let candidates = words
+        .iter()
+        .enumerate()
+        .filter(|(_, word)| word.0.len() == grid_size)
+        .map(|(i, _)| i)
+        .collect::<Vec<_>>();
+    let mut keep = vec![true; words.len()];
+    if grid_size < 2 {
+        return keep;
+    }
+
+    let chunk = |word: usize, position: usize| words[word].0[position].0 as usize;
+    // `supports[(j * grid_size + i) * token_count + t]` counts the words that can still be
+    // row `j` and have the token `t` at position `i`.
+    let support =
+        |row: usize, position: usize, tkn: usize| (row * grid_size + position) * token_count + tkn;
+    let mut supports = vec![0u32; grid_size * grid_size * token_count];
+    // The words with the token `t` at position `i`, at `i * token_count + t`.
+    let mut with_chunk = vec![vec![]; grid_size * token_count];
+    for &word in &candidates {
+        for position in 0..grid_size {
+            with_chunk[position * token_count + chunk(word, position)].push(word);
+            for row in 0..grid_size {
+                if row != position {
+                    supports[support(row, position, chunk(word, position))] += 1;
+                }
+            }
+        }
+    }
+
+    // `rows[word * grid_size + i]` is true while the word can be row `i`.
+    let mut rows = vec![false; words.len() * grid_size];
+    let mut removed = vec![];
+    for &word in &candidates {
+        for row in 0..grid_size {
+            rows[word * grid_size + row] = true;
+            let supported = (0..grid_size)
+                .all(|other| other == row || supports[support(other, row, chunk(word, other))] > 0);
+            if !supported {
+                removed.push((word, row));
+            }
+        }
+    }
+
+    while let Some((word, row)) = removed.pop() {
+        if !std::mem::take(&mut rows[word * grid_size + row]) {
+            continue;
+        }
+        for position in (0..grid_size).filter(|&position| position != row) {
+            let tkn = chunk(word, position);
+            let count = &mut supports[support(row, position, tkn)];
+            *count -= 1;
+            if *count == 0 {
+                // No word of this row has the chunk at `position` anymore, so no word with
+                // the chunk at position `row` can be row `position`.
+                removed.extend(
+                    with_chunk[row * token_count + tkn]
+                        .iter()
+                        .filter(|&&other| rows[other * grid_size + position])
+                        .map(|&other| (other, position)),
+                );
+            }
+        }
+    }
+
+    for &word in &candidates {
+        keep[word] = rows[word * grid_size..(word + 1) * grid_size]
+            .iter()
+            .any(|&row| row);
+    }
+    keep
+}
@@ -1,4 +1,4 @@
-use super::{matrix::TokenMatrix, wordfilter::Hr};
+use super::{chunkify, matrix::TokenMatrix, wordfilter::Hr};
 use dashmap::DashMap;
 use radix_trie::TrieKey;
 
@@ -63,6 +63,23 @@ impl Tokens {
         self.size
     }
 
+    /// Checks if the `Tokens` instance has no tokens.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    /// Returns every token with its string, ordered by token.
+    pub fn entries(&self) -> Vec<(Token, String)> {
+        let mut entries = self
+            .decode
+            .iter()
+            .map(|entry| (*entry.key(), entry.value().clone()))
+            .collect::<Vec<_>>();
+        entries.sort_unstable_by_key(|(tkn, _)| tkn.0);
+        entries
+    }
+
     /// Convert a token matrix into a string.
     #[inline]
     pub fn stringify_token_matrix(&self, tkn_matrix: TokenMatrix, chunk_size: usize) -> String {
@@ -96,19 +113,31 @@ impl Tokens {
         self.decode.get(&tkn).map(|s| s.value().clone())
     }
 
-    /// Tokenize a str into a token.
+    /// Tokenize a str into a token word, chunked like the dictionary words by `chunkify`.
+    /// Returns `None` if one of the chunks is not a known token.
     #[inline]
     pub fn tokenize_str(&self, s: &str, chunk_size: usize) -> Option<TokenWord> {
         let mut tkn_word = TokenWord::with_capacity(s.len() / chunk_size);
-        for chunk in s.chars().collect::<Vec<char>>().chunks(chunk_size) {
-            let chunk_str = chunk.iter().collect::<String>();
-            let tkn = *self.encode.get(&chunk_str).unwrap().value();
+        for chunk_str in chunkify(s, chunk_size) {
+            let tkn = *self.encode.get(&chunk_str)?.value();
             tkn_word.push(tkn);
         }
         Some(tkn_word)
     }
 }
 
+impl Default for TokenWord {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Default for Tokens {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl TrieKey for Token {
     #[inline]
     fn encode_bytes(&self) -> Vec<u8> {
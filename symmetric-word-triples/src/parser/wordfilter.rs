@@ -1,11 +1,24 @@
+#[cfg(feature = "native")]
+use super::index::MappedWords;
+use super::memo::{MemoStrategy, MemoTable};
+use super::prune;
 use super::token::{TokenWord, Tokens};
+#[cfg(feature = "native")]
+use crate::graph::{CooccurrenceGraph, TokenCooccurrences};
 use crate::parser::matrix::TokenMatrix;
 use crate::parser::{self};
 use ahash::AHasher;
-use dashmap::DashMap;
 use radix_trie::{Trie, TrieCommon};
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+#[cfg(feature = "native")]
+use rayon::prelude::*;
+use std::collections::HashSet;
 use std::hash::BuildHasherDefault;
-use std::sync::Arc;
+use std::ops::ControlFlow;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Instant;
 
 pub type ChunkyWord = Vec<String>;
 pub type WordDict = Vec<String>;
@@ -13,95 +26,899 @@ pub type ChunkyWordDict = Vec<ChunkyWord>;
 pub type WordTupleDict = Vec<String>;
 pub type Hr = BuildHasherDefault<AHasher>;
 
+/// How many solutions a search collects and in which order it visits the candidates.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SearchMode {
+    /// Enumerate every solution.
+    All,
+    /// Stop after the first `limit` solutions in search order.
+    First { limit: usize },
+    /// Shuffle the candidates of every row with a rng seeded by `seed` and `stream`,
+    /// and stop after `limit` solutions. The same arguments give the same sample.
+    Sample {
+        limit: usize,
+        seed: u64,
+        stream: u64,
+    },
+}
+
+impl SearchMode {
+    /// The maximum number of solutions this mode collects.
+    #[inline]
+    pub fn limit(&self) -> usize {
+        match self {
+            SearchMode::All => usize::MAX,
+            SearchMode::First { limit } | SearchMode::Sample { limit, .. } => *limit,
+        }
+    }
+
+    /// The same mode, but with its rng on another stream.
+    /// Used to give every seed word of a file its own reproducible sample.
+    #[inline]
+    pub fn with_stream(self, stream: u64) -> SearchMode {
+        match self {
+            SearchMode::Sample { limit, seed, .. } => SearchMode::Sample {
+                limit,
+                seed,
+                stream,
+            },
+            mode => mode,
+        }
+    }
+
+    pub(crate) fn rng(&self) -> Option<ChaCha8Rng> {
+        match self {
+            SearchMode::Sample { seed, stream, .. } => {
+                let mut rng = ChaCha8Rng::seed_from_u64(*seed);
+                rng.set_stream(*stream);
+                Some(rng)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Why a search stopped before it visited the whole search space.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SearchStop {
+    /// The search mode's solution limit was reached.
+    Limit,
+    /// The deadline passed.
+    Deadline,
+}
+
+/// A square whose rows are words but that is symmetric only up to some cells.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NearMiss {
+    pub square: TokenMatrix,
+    /// The cells `(row, column)` above the diagonal that differ from `(column, row)`.
+    pub mismatches: Vec<(usize, usize)>,
+}
+
+/// Counters of the searches of a prefix map, for reports.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SearchStats {
+    /// Calls of `backtrack`.
+    pub nodes: u64,
+    /// Nodes that looked up candidates for their next row, by number of filled rows.
+    pub expanded_per_depth: Vec<u64>,
+    /// Candidates of those nodes, by number of filled rows.
+    pub candidates_per_depth: Vec<u64>,
+    /// Prefix lookups answered by the memo table.
+    pub table_hits: u64,
+    /// Prefix lookups that went to the trie and filled the memo table.
+    pub table_misses: u64,
+}
+
+impl SearchStats {
+    #[inline]
+    fn expanded(&mut self, depth: usize, candidates: usize) {
+        if self.expanded_per_depth.len() <= depth {
+            self.expanded_per_depth.resize(depth + 1, 0);
+            self.candidates_per_depth.resize(depth + 1, 0);
+        }
+        self.expanded_per_depth[depth] += 1;
+        self.candidates_per_depth[depth] += candidates as u64;
+    }
+
+    /// Adds the counters of another search.
+    pub fn merge(&mut self, other: &SearchStats) {
+        self.nodes += other.nodes;
+        let depths = other.expanded_per_depth.len();
+        if self.expanded_per_depth.len() < depths {
+            self.expanded_per_depth.resize(depths, 0);
+            self.candidates_per_depth.resize(depths, 0);
+        }
+        for depth in 0..depths {
+            self.expanded_per_depth[depth] += other.expanded_per_depth[depth];
+            self.candidates_per_depth[depth] += other.candidates_per_depth[depth];
+        }
+        self.table_hits += other.table_hits;
+        self.table_misses += other.table_misses;
+    }
+
+    /// Average number of candidates per expanded node, by number of filled rows.
+    pub fn branching_per_depth(&self) -> Vec<f64> {
+        self.expanded_per_depth
+            .iter()
+            .zip(&self.candidates_per_depth)
+            .map(|(&expanded, &candidates)| match expanded {
+                0 => 0.0,
+                _ => candidates as f64 / expanded as f64,
+            })
+            .collect()
+    }
+
+    /// Share of the prefix lookups answered by the memo table, if there were any.
+    pub fn table_hit_rate(&self) -> Option<f64> {
+        let lookups = self.table_hits + self.table_misses;
+        (lookups > 0).then(|| self.table_hits as f64 / lookups as f64)
+    }
+}
+
 pub trait WordFilter {
     fn symmetric_words_single(
         &self,
         dictionary_word: TokenWord,
     ) -> Result<Vec<Arc<TokenMatrix>>, String>;
+
+    fn symmetric_words_single_with(
+        &self,
+        dictionary_word: TokenWord,
+        mode: SearchMode,
+    ) -> Result<Vec<Arc<TokenMatrix>>, String>;
+
+    fn count_symmetric_words_single(&self, dictionary_word: TokenWord) -> Result<usize, String>;
+
+    fn symmetric_words_single_until(
+        &self,
+        dictionary_word: TokenWord,
+        mode: SearchMode,
+        deadline: Instant,
+    ) -> Result<(Vec<Arc<TokenMatrix>>, bool), String>;
+
+    fn count_symmetric_words_single_until(
+        &self,
+        dictionary_word: TokenWord,
+        deadline: Instant,
+    ) -> Result<(usize, bool), String>;
+}
+
+/// Every way to put `words` different things into distinct rows of `rows`,
+/// as the row of each thing in order.
+fn row_assignments(rows: usize, words: usize) -> Vec<Vec<usize>> {
+    if words == 0 {
+        return vec![vec![]];
+    }
+    row_assignments(rows, words - 1)
+        .into_iter()
+        .flat_map(|assignment| {
+            (0..rows)
+                .filter(|row| !assignment.contains(row))
+                .map(|row| [assignment.as_slice(), &[row]].concat())
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// Where the words of a prefix map are looked up.
+enum WordIndex {
+    Trie(Trie<TokenWord, ()>),
+    /// A memory-mapped index file, queried in place.
+    #[cfg(feature = "native")]
+    Mapped(MappedWords),
 }
 
 pub struct PrefixMap {
     tokens: Tokens,
-    trie: Trie<TokenWord, ()>,
+    words: WordIndex,
+    /// Dictionary words that arc consistency showed can't be any row of a square.
+    /// They are not in `words`, so the searches never try them.
+    pruned: HashSet<TokenWord, Hr>,
+    /// The prefix map with the pruned words put back, built by the first search that needs it.
+    unpruned: OnceLock<Box<PrefixMap>>,
     grid_size: usize,
     chunk_size: usize,
-    table: DashMap<TokenWord, Vec<Arc<TokenWord>>, Hr>,
-    use_table: bool,
+    memo: MemoTable,
+    stats: Mutex<SearchStats>,
 }
 
 impl PrefixMap {
     /// Creates a new prefix map with the word dictionary and the grid_ and chunk_size.
-    pub fn new(dict: &WordDict, grid_size: usize, chunk_size: usize, use_table: bool) -> PrefixMap {
+    /// `memo` says how the prefix lookups of the searches are memoized.
+    pub fn new(
+        dict: &WordDict,
+        grid_size: usize,
+        chunk_size: usize,
+        memo: MemoStrategy,
+    ) -> PrefixMap {
         let mut tokens = Tokens::new();
-        let chunky_dict = parser::chunkify_dict(dict, grid_size, chunk_size);
-        let token_dict = chunky_dict
-            .iter()
-            .map(|chunky| {
-                chunky
-                    .iter()
-                    .map(|chunk| tokens.insert(chunk.clone()))
-                    .collect::<TokenWord>()
-            })
-            .collect::<Vec<_>>();
+        let token_dict = parser::tokenize_dict(dict, grid_size, chunk_size, &mut tokens);
 
+        PrefixMap::from_token_words(tokens, token_dict, grid_size, chunk_size, memo)
+    }
+
+    /// Creates a prefix map from words that are already tokenized with `tokens`.
+    /// Words that can't be any row of a square are pruned before they go into the trie.
+    pub(crate) fn from_token_words<I>(
+        tokens: Tokens,
+        token_dict: I,
+        grid_size: usize,
+        chunk_size: usize,
+        memo: MemoStrategy,
+    ) -> PrefixMap
+    where
+        I: IntoIterator<Item = TokenWord>,
+    {
         let mut trie = Trie::new();
         for tkn_word in token_dict {
             trie.insert(tkn_word, ());
         }
         // println!("{:?}", trie);
 
-        let table: DashMap<_, _, Hr> = DashMap::default();
+        let words = trie.keys().collect::<Vec<_>>();
+        let keep = prune::arc_consistent_words(&words, grid_size, tokens.len() as usize);
+        let pruned = words
+            .into_iter()
+            .zip(keep)
+            .filter(|(_, keep)| !keep)
+            .map(|(word, _)| word.clone())
+            .collect::<HashSet<_, Hr>>();
+        for word in &pruned {
+            trie.remove(word);
+        }
+
+        PrefixMap::with_words(
+            tokens,
+            WordIndex::Trie(trie),
+            pruned,
+            grid_size,
+            chunk_size,
+            memo,
+        )
+    }
+
+    /// Creates a prefix map from the words of an index, which were pruned before they
+    /// were written.
+    pub(crate) fn from_pruned_words<I>(
+        tokens: Tokens,
+        token_dict: I,
+        pruned: HashSet<TokenWord, Hr>,
+        grid_size: usize,
+        chunk_size: usize,
+        memo: MemoStrategy,
+    ) -> PrefixMap
+    where
+        I: IntoIterator<Item = TokenWord>,
+    {
+        let mut trie = Trie::new();
+        for tkn_word in token_dict {
+            trie.insert(tkn_word, ());
+        }
+        PrefixMap::with_words(
+            tokens,
+            WordIndex::Trie(trie),
+            pruned,
+            grid_size,
+            chunk_size,
+            memo,
+        )
+    }
+
+    /// Creates a prefix map over the words of a memory-mapped index.
+    #[cfg(feature = "native")]
+    pub(crate) fn from_mapped_words(
+        tokens: Tokens,
+        words: MappedWords,
+        pruned: HashSet<TokenWord, Hr>,
+        grid_size: usize,
+        chunk_size: usize,
+        memo: MemoStrategy,
+    ) -> PrefixMap {
+        PrefixMap::with_words(
+            tokens,
+            WordIndex::Mapped(words),
+            pruned,
+            grid_size,
+            chunk_size,
+            memo,
+        )
+    }
+
+    fn with_words(
+        tokens: Tokens,
+        words: WordIndex,
+        pruned: HashSet<TokenWord, Hr>,
+        grid_size: usize,
+        chunk_size: usize,
+        memo: MemoStrategy,
+    ) -> PrefixMap {
         PrefixMap {
             tokens,
-            trie,
+            words,
             grid_size,
             chunk_size,
-            table,
-            use_table,
+            pruned,
+            unpruned: OnceLock::new(),
+            memo: MemoTable::new(memo),
+            stats: Mutex::default(),
+        }
+    }
+
+    /// Number of dictionary words pruned before the search because they can't be in a square.
+    pub fn pruned_words(&self) -> usize {
+        self.pruned.len()
+    }
+
+    pub(crate) fn pruned_token_words(&self) -> impl Iterator<Item = &TokenWord> {
+        self.pruned.iter()
+    }
+
+    /// A copy of the prefix map with the pruned words put back, for searches that don't need
+    /// the rows to be symmetric. Built once, by the first search that asks for it.
+    /// `None` if nothing was pruned.
+    fn unpruned(&self) -> Option<&PrefixMap> {
+        if self.pruned.is_empty() {
+            return None;
         }
+        let unpruned = self.unpruned.get_or_init(|| {
+            let mut trie = Trie::new();
+            for word in self
+                .get_prefix_words(&TokenWord::new())
+                .iter()
+                .map(|word| (**word).clone())
+                .chain(self.pruned.iter().cloned())
+            {
+                trie.insert(word, ());
+            }
+            Box::new(PrefixMap::with_words(
+                self.tokens.clone(),
+                WordIndex::Trie(trie),
+                HashSet::default(),
+                self.grid_size,
+                self.chunk_size,
+                self.memo_strategy(),
+            ))
+        });
+        Some(unpruned)
+    }
+
+    /// Moves the counters of a search of the unpruned map to this one.
+    fn take_unpruned_stats(&self, unpruned: &PrefixMap) {
+        let stats = std::mem::take(&mut *unpruned.stats.lock().unwrap());
+        self.stats.lock().unwrap().merge(&stats);
     }
 
     #[inline]
     pub fn get(&self, key: &TokenWord) -> Option<Vec<Arc<TokenWord>>> {
-        self.table.get(key).map(|v| v.value().clone())
+        self.memo.get(key)
+    }
+
+    /// How the prefix lookups of the searches are memoized.
+    pub fn memo_strategy(&self) -> MemoStrategy {
+        self.memo.strategy()
+    }
+
+    /// False if there is no memo table, or an `Auto` table is paused.
+    pub fn memo_enabled(&self) -> bool {
+        self.memo.is_enabled()
+    }
+
+    /// Number of prefixes in the memo table.
+    pub fn memo_len(&self) -> usize {
+        self.memo.len()
     }
 
     /// Returns an iterator over all words with the given prefix.
     #[inline]
     pub fn get_prefix_words(&self, prefix: &TokenWord) -> Vec<Arc<TokenWord>> {
-        let _use_table = self.use_table;
+        match &self.words {
+            WordIndex::Trie(trie) => trie
+                .get_raw_descendant(prefix)
+                .into_iter()
+                .flat_map(|subtrie| subtrie.keys())
+                .map(|x| Arc::new(x.clone()))
+                .collect::<Vec<_>>(),
+            #[cfg(feature = "native")]
+            WordIndex::Mapped(words) => words.prefix_words(prefix),
+        }
+    }
 
-        let prefixes = self
-            .trie
-            .get_raw_descendant(prefix)
-            .into_iter()
-            .flat_map(|subtrie| subtrie.keys())
-            .map(|x| Arc::new(x.clone()))
-            .collect::<Vec<_>>();
+    /// Returns all words with the given prefix, going through the memo table if it is enabled.
+    #[inline]
+    pub fn get_prefix_words_table(&self, prefix: &TokenWord) -> Vec<Arc<TokenWord>> {
+        if !self.memo.should_lookup() {
+            return self.get_prefix_words(prefix);
+        }
+        // First check if the key is already in the table.
+        if let Some(chunky_words) = self.get(prefix) {
+            return chunky_words;
+        }
+
+        let prefixes = self.get_prefix_words(prefix);
+
+        self.memo.insert(prefix.clone(), prefixes.clone());
 
         prefixes
     }
 
-    /// Returns an iterator over all words with the given prefix.
+    /// Returns the words with the given prefix, going through the table if it is enabled.
     #[inline]
-    pub fn get_prefix_words_table(&self, prefix: &TokenWord) -> Vec<Arc<TokenWord>> {
-        // First check if the key is already in the table.
+    fn prefixed_words(&self, prefix: &TokenWord) -> Vec<Arc<TokenWord>> {
+        self.get_prefix_words_table(prefix)
+    }
 
+    /// Same as `prefixed_words`, but counts the table hits and misses.
+    #[inline]
+    fn prefixed_words_counted(
+        &self,
+        prefix: &TokenWord,
+        stats: &mut SearchStats,
+    ) -> Vec<Arc<TokenWord>> {
+        if !self.memo.should_lookup() {
+            return self.get_prefix_words(prefix);
+        }
         if let Some(chunky_words) = self.get(prefix) {
+            stats.table_hits += 1;
             return chunky_words;
         }
+        stats.table_misses += 1;
+        let prefixes = self.get_prefix_words(prefix);
+        self.memo.insert(prefix.clone(), prefixes.clone());
+        prefixes
+    }
 
-        let prefixes = self
-            .trie
-            .get_raw_descendant(prefix)
-            .into_iter()
-            .flat_map(|subtrie| subtrie.keys())
-            .map(|x| Arc::new(x.clone()))
-            .collect::<Vec<_>>();
+    /// The counters of all searches of `symmetric_words_single*` and
+    /// `count_symmetric_words_single*` since the prefix map was built or the stats were reset.
+    pub fn stats(&self) -> SearchStats {
+        self.stats.lock().unwrap().clone()
+    }
 
-        self.table.insert(prefix.clone(), prefixes.clone());
+    pub fn reset_stats(&self) {
+        *self.stats.lock().unwrap() = SearchStats::default();
+    }
 
-        prefixes
+    /// Fills the remaining rows of `solution_matrix` in every possible way and calls
+    /// `on_solution` for each full symmetric matrix, until it returns `Break`.
+    /// `fixed` holds the words some rows have to be, if any. The rows before a fixed row
+    /// only get candidates that match its word in the fixed column.
+    /// With a rng the candidates of every row are visited in shuffled order.
+    /// With a deadline the search breaks as soon as it has passed.
+    fn backtrack<F>(
+        &self,
+        solution_matrix: &mut TokenMatrix,
+        fixed: &[Option<TokenWord>],
+        rng: &mut Option<ChaCha8Rng>,
+        deadline: Option<Instant>,
+        stats: &mut SearchStats,
+        on_solution: &mut F,
+    ) -> ControlFlow<SearchStop>
+    where
+        F: FnMut(&TokenMatrix) -> ControlFlow<SearchStop>,
+    {
+        stats.nodes += 1;
+        if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            return ControlFlow::Break(SearchStop::Deadline);
+        }
+        if solution_matrix.is_full() {
+            if solution_matrix.is_symmetric() {
+                return on_solution(solution_matrix);
+            }
+            return ControlFlow::Continue(());
+        }
+        let depth = solution_matrix.len();
+        let next_prefix = if solution_matrix.is_empty() {
+            TokenWord::new()
+        } else {
+            parser::next_prefix(solution_matrix)
+        };
+
+        let mut prefixed_words = match fixed.get(depth) {
+            Some(Some(word)) if word.0.starts_with(&next_prefix.0) => vec![Arc::new(word.clone())],
+            Some(Some(_)) => vec![],
+            _ => self.prefixed_words_counted(&next_prefix, stats),
+        };
+        if fixed.iter().skip(depth + 1).any(Option::is_some) {
+            prefixed_words.retain(|candidate| {
+                fixed.iter().enumerate().skip(depth + 1).all(|(row, word)| {
+                    word.as_ref()
+                        .is_none_or(|word| candidate.0[row] == word.0[depth])
+                })
+            });
+        }
+        stats.expanded(depth, prefixed_words.len());
+        if let Some(rng) = rng {
+            prefixed_words.shuffle(rng);
+        }
+        for word in prefixed_words {
+            solution_matrix.push((*word).clone()).unwrap();
+            let flow = self.backtrack(solution_matrix, fixed, rng, deadline, stats, on_solution);
+            solution_matrix.pop();
+            flow?;
+        }
+        ControlFlow::Continue(())
+    }
+
+    /// Collects the solutions with `word` in the first row that `mode` asks for.
+    /// The flag is false if the deadline cut the search short.
+    fn search_single(
+        &self,
+        word: TokenWord,
+        mode: SearchMode,
+        deadline: Option<Instant>,
+    ) -> Result<(Vec<Arc<TokenMatrix>>, bool), String> {
+        let limit = mode.limit();
+        if self.grid_size == 0 || limit == 0 {
+            return Ok((vec![], true));
+        }
+        let mut solution_set = vec![];
+        let mut solution_matrix = TokenMatrix::new(self.grid_size);
+        solution_matrix.push(word)?;
+
+        let mut stats = SearchStats::default();
+        let flow = self.backtrack(
+            &mut solution_matrix,
+            &[],
+            &mut mode.rng(),
+            deadline,
+            &mut stats,
+            &mut |solution| {
+                solution_set.push(Arc::new(solution.clone()));
+                if solution_set.len() >= limit {
+                    ControlFlow::Break(SearchStop::Limit)
+                } else {
+                    ControlFlow::Continue(())
+                }
+            },
+        );
+        self.stats.lock().unwrap().merge(&stats);
+        Ok((
+            solution_set,
+            flow != ControlFlow::Break(SearchStop::Deadline),
+        ))
+    }
+
+    /// Counts the solutions with `word` in the first row.
+    /// The flag is false if the deadline cut the search short.
+    fn count_single(
+        &self,
+        word: TokenWord,
+        deadline: Option<Instant>,
+    ) -> Result<(usize, bool), String> {
+        if self.grid_size == 0 {
+            return Ok((0, true));
+        }
+        let mut solution_count = 0;
+        let mut solution_matrix = TokenMatrix::new(self.grid_size);
+        solution_matrix.push(word)?;
+
+        let mut stats = SearchStats::default();
+        let flow = self.backtrack(
+            &mut solution_matrix,
+            &[],
+            &mut None,
+            deadline,
+            &mut stats,
+            &mut |_| {
+                solution_count += 1;
+                ControlFlow::Continue(())
+            },
+        );
+        self.stats.lock().unwrap().merge(&stats);
+        Ok((solution_count, flow.is_continue()))
+    }
+
+    /// Calls `on_solution` for every solution that contains all of `words`, exploring every
+    /// assignment of the words to rows. A solution is only taken with the assignment of the
+    /// first row every word is in, so it is found once even if a word repeats.
+    fn search_containing<F>(
+        &self,
+        words: &[TokenWord],
+        rng: &mut Option<ChaCha8Rng>,
+        stats: &mut SearchStats,
+        on_solution: &mut F,
+    ) -> ControlFlow<SearchStop>
+    where
+        F: FnMut(&TokenMatrix) -> ControlFlow<SearchStop>,
+    {
+        for rows in row_assignments(self.grid_size, words.len()) {
+            let mut fixed = vec![None; self.grid_size];
+            for (&row, word) in rows.iter().zip(words) {
+                fixed[row] = Some(word.clone());
+            }
+            if !self.fixed_rows_fit(&fixed) {
+                continue;
+            }
+            self.backtrack(
+                &mut TokenMatrix::new(self.grid_size),
+                &fixed,
+                rng,
+                None,
+                stats,
+                &mut |solution| {
+                    let first_rows = rows
+                        .iter()
+                        .zip(words)
+                        .all(|(&row, word)| (0..row).all(|i| solution.get_row(i) != word.0));
+                    if first_rows {
+                        on_solution(solution)
+                    } else {
+                        ControlFlow::Continue(())
+                    }
+                },
+            )?;
+        }
+        ControlFlow::Continue(())
+    }
+
+    /// Checks that fixed rows can be in one solution before searching the other rows:
+    /// every two of them have to agree where they cross, and if the first rows are fixed,
+    /// every later row needs words starting with the chunks they fix.
+    fn fixed_rows_fit(&self, fixed: &[Option<TokenWord>]) -> bool {
+        for (a, word_a) in fixed.iter().enumerate() {
+            for (b, word_b) in fixed.iter().enumerate().skip(a + 1) {
+                if let (Some(word_a), Some(word_b)) = (word_a, word_b) {
+                    if word_a.0[b] != word_b.0[a] {
+                        return false;
+                    }
+                }
+            }
+        }
+        let leading = fixed.iter().map_while(Option::as_ref).collect::<Vec<_>>();
+        (leading.len()..self.grid_size).all(|row| {
+            fixed[row].is_some() || {
+                let prefix = leading.iter().map(|word| word.0[row]).collect();
+                !self.prefixed_words(&prefix).is_empty()
+            }
+        })
+    }
+
+    /// Collects the solutions that `mode` asks for with `word` in any row, and so in the
+    /// same column. The word constrains the other rows during the search.
+    pub fn symmetric_words_containing(
+        &self,
+        word: TokenWord,
+        mode: SearchMode,
+    ) -> Result<Vec<Arc<TokenMatrix>>, String> {
+        self.symmetric_words_containing_all(&[word], mode)
+    }
+
+    /// Counts the solutions with `word` in any row.
+    pub fn count_symmetric_words_containing(&self, word: TokenWord) -> Result<usize, String> {
+        self.count_symmetric_words_containing_all(&[word])
+    }
+
+    /// Collects the solutions that `mode` asks for that contain all of `words`, each in
+    /// some row. There are none if there are more different words than rows.
+    pub fn symmetric_words_containing_all(
+        &self,
+        words: &[TokenWord],
+        mode: SearchMode,
+    ) -> Result<Vec<Arc<TokenMatrix>>, String> {
+        let words = self.required_words(words)?;
+        let limit = mode.limit();
+        let mut solution_set = vec![];
+        if limit == 0 {
+            return Ok(solution_set);
+        }
+        let mut stats = SearchStats::default();
+        let flow = self.search_containing(&words, &mut mode.rng(), &mut stats, &mut |solution| {
+            solution_set.push(Arc::new(solution.clone()));
+            if solution_set.len() >= limit {
+                ControlFlow::Break(SearchStop::Limit)
+            } else {
+                ControlFlow::Continue(())
+            }
+        });
+        self.stats.lock().unwrap().merge(&stats);
+        match flow {
+            ControlFlow::Continue(()) | ControlFlow::Break(SearchStop::Limit) => Ok(solution_set),
+            ControlFlow::Break(stop) => Err(format!("The search stopped early: {stop:?}.")),
+        }
+    }
+
+    /// Counts the solutions that contain all of `words`.
+    pub fn count_symmetric_words_containing_all(
+        &self,
+        words: &[TokenWord],
+    ) -> Result<usize, String> {
+        let words = self.required_words(words)?;
+        let mut solution_count = 0;
+        let mut stats = SearchStats::default();
+        let flow = self.search_containing(&words, &mut None, &mut stats, &mut |_| {
+            solution_count += 1;
+            ControlFlow::Continue(())
+        });
+        self.stats.lock().unwrap().merge(&stats);
+        match flow {
+            ControlFlow::Continue(()) => Ok(solution_count),
+            ControlFlow::Break(stop) => Err(format!("The search stopped early: {stop:?}.")),
+        }
+    }
+
+    /// Collects the solutions that `mode` asks for in which row `i` is `fixed[i]` wherever
+    /// that is a word, and which `accept` takes. The fixed rows constrain the other rows
+    /// during the search, which stops once `deadline` has passed.
+    /// The flag is false if the deadline cut the search short.
+    pub fn symmetric_words_fixed_until<A>(
+        &self,
+        fixed: &[Option<TokenWord>],
+        mode: SearchMode,
+        deadline: Instant,
+        mut accept: A,
+    ) -> Result<(Vec<Arc<TokenMatrix>>, bool), String>
+    where
+        A: FnMut(&TokenMatrix) -> bool,
+    {
+        if fixed.len() > self.grid_size {
+            return Err(format!(
+                "{} fixed rows don't fit into a grid of {} rows.",
+                fixed.len(),
+                self.grid_size
+            ));
+        }
+        let limit = mode.limit();
+        let mut fixed = fixed.to_vec();
+        fixed.resize(self.grid_size, None);
+        if self.grid_size == 0
+            || limit == 0
+            || !fixed.iter().flatten().all(|word| self.contains(word))
+            || !self.fixed_rows_fit(&fixed)
+        {
+            return Ok((vec![], true));
+        }
+
+        let mut solution_set = vec![];
+        let mut stats = SearchStats::default();
+        let flow = self.backtrack(
+            &mut TokenMatrix::new(self.grid_size),
+            &fixed,
+            &mut mode.rng(),
+            Some(deadline),
+            &mut stats,
+            &mut |solution| {
+                if accept(solution) {
+                    solution_set.push(Arc::new(solution.clone()));
+                    if solution_set.len() >= limit {
+                        return ControlFlow::Break(SearchStop::Limit);
+                    }
+                }
+                ControlFlow::Continue(())
+            },
+        );
+        self.stats.lock().unwrap().merge(&stats);
+        Ok((
+            solution_set,
+            flow != ControlFlow::Break(SearchStop::Deadline),
+        ))
+    }
+
+    /// Collects the squares with `word` in the first row that `mode` asks for, whose rows are
+    /// words and in which at most `max_mismatches` cells above the diagonal differ from their
+    /// mirror cell. Perfect squares are included, with no mismatches.
+    /// While mismatches are left, every word is tried as the next row instead of only
+    /// the words with the column prefix, so this is meant for small dictionaries.
+    pub fn near_symmetric_words_single(
+        &self,
+        word: TokenWord,
+        max_mismatches: usize,
+        mode: SearchMode,
+    ) -> Result<Vec<NearMiss>, String> {
+        let limit = mode.limit();
+        if self.grid_size == 0 || limit == 0 {
+            return Ok(vec![]);
+        }
+        if max_mismatches > 0 {
+            if let Some(unpruned) = self.unpruned() {
+                let near_misses = unpruned.near_symmetric_words_single(word, max_mismatches, mode);
+                self.take_unpruned_stats(unpruned);
+                return near_misses;
+            }
+        }
+        let mut near_misses = vec![];
+        let mut solution_matrix = TokenMatrix::new(self.grid_size);
+        solution_matrix.push(word)?;
+
+        let mut stats = SearchStats::default();
+        let _ = self.backtrack_near(
+            &mut solution_matrix,
+            max_mismatches,
+            &mut vec![],
+            &mut mode.rng(),
+            &mut stats,
+            &mut |square, mismatches| {
+                near_misses.push(NearMiss {
+                    square: square.clone(),
+                    mismatches: mismatches.to_vec(),
+                });
+                if near_misses.len() >= limit {
+                    ControlFlow::Break(SearchStop::Limit)
+                } else {
+                    ControlFlow::Continue(())
+                }
+            },
+        );
+        self.stats.lock().unwrap().merge(&stats);
+        Ok(near_misses)
+    }
+
+    /// Like `backtrack`, but the rows may differ from their column prefixes in up to
+    /// `max_mismatches` cells altogether, which are recorded in `mismatches`.
+    fn backtrack_near<F>(
+        &self,
+        solution_matrix: &mut TokenMatrix,
+        max_mismatches: usize,
+        mismatches: &mut Vec<(usize, usize)>,
+        rng: &mut Option<ChaCha8Rng>,
+        stats: &mut SearchStats,
+        on_solution: &mut F,
+    ) -> ControlFlow<SearchStop>
+    where
+        F: FnMut(&TokenMatrix, &[(usize, usize)]) -> ControlFlow<SearchStop>,
+    {
+        stats.nodes += 1;
+        if solution_matrix.is_full() {
+            return on_solution(solution_matrix, mismatches);
+        }
+        let depth = solution_matrix.len();
+        let next_prefix = parser::next_prefix(solution_matrix);
+        let budget = max_mismatches - mismatches.len();
+
+        let candidates_prefix = if budget == 0 {
+            next_prefix.clone()
+        } else {
+            TokenWord::new()
+        };
+        let mut candidates = self.prefixed_words_counted(&candidates_prefix, stats);
+        stats.expanded(depth, candidates.len());
+        if let Some(rng) = rng {
+            candidates.shuffle(rng);
+        }
+        for word in candidates {
+            let differing = (0..depth)
+                .filter(|&row| word.0[row] != next_prefix.0[row])
+                .collect::<Vec<_>>();
+            if differing.len() > budget {
+                continue;
+            }
+            let mismatch_count = mismatches.len();
+            mismatches.extend(differing.iter().map(|&row| (row, depth)));
+            solution_matrix.push((*word).clone()).unwrap();
+            let flow = self.backtrack_near(
+                solution_matrix,
+                max_mismatches,
+                mismatches,
+                rng,
+                stats,
+                on_solution,
+            );
+            solution_matrix.pop();
+            mismatches.truncate(mismatch_count);
+            flow?;
+        }
+        ControlFlow::Continue(())
+    }
+
+    /// Checks that the required words are words of the grid size and removes repeated ones.
+    fn required_words(&self, words: &[TokenWord]) -> Result<Vec<TokenWord>, String> {
+        let mut required = vec![];
+        for word in words {
+            if word.0.len() != self.grid_size {
+                return Err(format!(
+                    "The word has size {} and the grid size is {}! Word: {word:?}",
+                    word.0.len(),
+                    self.grid_size
+                ));
+            }
+            // Fixed rows don't go through the trie, so they have to be words already.
+            if !self.contains(word) {
+                return Err(format!("The word is not in the dictionary! Word: {word:?}"));
+            }
+            if !required.contains(word) {
+                required.push(word.clone());
+            }
+        }
+        Ok(required)
     }
 
     #[inline]
@@ -110,10 +927,203 @@ impl PrefixMap {
             .stringify_token_matrix(tkn_matrix, self.chunk_size)
     }
 
+    /// Searches with every word of the dictionary in the first row, in parallel.
+    /// Every seed word gets its own rng stream in sample mode.
+    #[cfg(feature = "native")]
+    pub fn symmetric_words_all(&self, mode: SearchMode) -> Vec<Arc<TokenMatrix>> {
+        self.get_prefix_words(&TokenWord::new())
+            .par_iter()
+            .enumerate()
+            .flat_map_iter(|(i, word)| {
+                self.symmetric_words_single_with((**word).clone(), mode.with_stream(i as u64))
+                    .unwrap()
+            })
+            .collect()
+    }
+
+    /// Builds the co-occurrence graph of all solutions, searching every word of the dictionary
+    /// in the first row in parallel. The solutions are added to the graph as they are found
+    /// instead of being collected.
+    #[cfg(feature = "native")]
+    pub fn cooccurrence_graph(&self) -> CooccurrenceGraph {
+        self.get_prefix_words(&TokenWord::new())
+            .par_iter()
+            .fold(TokenCooccurrences::default, |mut cooccurrences, word| {
+                let mut solution_matrix = TokenMatrix::new(self.grid_size);
+                if solution_matrix.push((**word).clone()).is_ok() {
+                    let mut stats = SearchStats::default();
+                    let _ = self.backtrack(
+                        &mut solution_matrix,
+                        &[],
+                        &mut None,
+                        None,
+                        &mut stats,
+                        &mut |solution| {
+                            cooccurrences.add(solution);
+                            ControlFlow::Continue(())
+                        },
+                    );
+                    self.stats.lock().unwrap().merge(&stats);
+                }
+                cooccurrences
+            })
+            .reduce(TokenCooccurrences::default, TokenCooccurrences::merge)
+            .stringify(self)
+    }
+
+    /// Same as `near_symmetric_words_single` with every word of the dictionary in the first row,
+    /// in parallel.
+    #[cfg(feature = "native")]
+    pub fn near_symmetric_words_all(
+        &self,
+        max_mismatches: usize,
+        mode: SearchMode,
+    ) -> Vec<NearMiss> {
+        if max_mismatches > 0 {
+            if let Some(unpruned) = self.unpruned() {
+                let near_misses = unpruned.near_symmetric_words_all(max_mismatches, mode);
+                self.take_unpruned_stats(unpruned);
+                return near_misses;
+            }
+        }
+        self.get_prefix_words(&TokenWord::new())
+            .par_iter()
+            .enumerate()
+            .flat_map_iter(|(i, word)| {
+                self.near_symmetric_words_single(
+                    (**word).clone(),
+                    max_mismatches,
+                    mode.with_stream(i as u64),
+                )
+                .unwrap()
+            })
+            .collect()
+    }
+
+    /// Returns an iterator that finds the solutions with `word` in the first row one at a time,
+    /// in the same order as `symmetric_words_single`.
+    pub fn symmetric_words_iter(
+        self: &Arc<Self>,
+        word: TokenWord,
+    ) -> Result<SymmetricWordsIter, String> {
+        let mut solution_matrix = TokenMatrix::new(self.grid_size);
+        let mut stack = vec![];
+        let mut pending = None;
+        if self.grid_size > 0 {
+            solution_matrix.push(word)?;
+            if solution_matrix.is_full() {
+                pending = solution_matrix
+                    .is_symmetric()
+                    .then(|| solution_matrix.clone());
+            } else {
+                let next_prefix = parser::next_prefix(&solution_matrix);
+                stack.push(self.prefixed_words(&next_prefix).into_iter());
+            }
+        }
+        Ok(SymmetricWordsIter {
+            prefix_map: Arc::clone(self),
+            solution_matrix,
+            stack,
+            pending,
+        })
+    }
+
+    /// Convert every row of a token matrix into a string.
+    #[inline]
+    pub fn stringify_token_rows(&self, tkn_matrix: &TokenMatrix) -> Vec<String> {
+        tkn_matrix
+            .rows()
+            .map(|row| self.tokens.stringify_token_word(row))
+            .collect()
+    }
+
+    #[inline]
+    pub fn stringify_token_word(&self, tkn_word: &TokenWord) -> String {
+        self.tokens.stringify_token_word(tkn_word.into())
+    }
+
     #[inline]
     pub fn tokenize_word(&self, word: &str) -> TokenWord {
         self.tokens.tokenize_str(word, self.chunk_size).unwrap()
     }
+
+    /// Tokenizes a word, returning `None` if one of its chunks is not a known token.
+    #[inline]
+    pub fn try_tokenize_word(&self, word: &str) -> Option<TokenWord> {
+        self.tokens.tokenize_str(word, self.chunk_size)
+    }
+
+    /// Checks if the word is one of the words of the prefix map, including pruned words.
+    #[inline]
+    pub fn contains(&self, word: &TokenWord) -> bool {
+        if word.0.len() != self.grid_size {
+            return false;
+        }
+        let in_words = match &self.words {
+            WordIndex::Trie(trie) => trie.get(word).is_some(),
+            #[cfg(feature = "native")]
+            WordIndex::Mapped(words) => words.contains(word),
+        };
+        in_words || self.pruned.contains(word)
+    }
+
+    #[inline]
+    pub fn tokens(&self) -> &Tokens {
+        &self.tokens
+    }
+
+    #[inline]
+    pub fn grid_size(&self) -> usize {
+        self.grid_size
+    }
+
+    #[inline]
+    pub fn chunk_size(&self) -> usize {
+        self.chunk_size
+    }
+}
+
+/// Lazily walks the same search tree as `backtrack`, keeping the candidates of every
+/// filled row on an explicit stack so the search can be resumed between solutions.
+pub struct SymmetricWordsIter {
+    prefix_map: Arc<PrefixMap>,
+    solution_matrix: TokenMatrix,
+    stack: Vec<std::vec::IntoIter<Arc<TokenWord>>>,
+    pending: Option<TokenMatrix>,
+}
+
+impl Iterator for SymmetricWordsIter {
+    type Item = TokenMatrix;
+
+    fn next(&mut self) -> Option<TokenMatrix> {
+        if let Some(solution) = self.pending.take() {
+            return Some(solution);
+        }
+        loop {
+            let Some(word) = self.stack.last_mut()?.next() else {
+                self.stack.pop();
+                if !self.stack.is_empty() {
+                    self.solution_matrix.pop();
+                }
+                continue;
+            };
+            self.solution_matrix.push((*word).clone()).unwrap();
+            if self.solution_matrix.is_full() {
+                let solution = self
+                    .solution_matrix
+                    .is_symmetric()
+                    .then(|| self.solution_matrix.clone());
+                self.solution_matrix.pop();
+                if solution.is_some() {
+                    return solution;
+                }
+            } else {
+                let next_prefix = parser::next_prefix(&self.solution_matrix);
+                self.stack
+                    .push(self.prefix_map.prefixed_words(&next_prefix).into_iter());
+            }
+        }
+    }
 }
 
 impl WordFilter for PrefixMap {
@@ -121,43 +1131,49 @@ impl WordFilter for PrefixMap {
     /// that word in the first row.
     #[inline]
     fn symmetric_words_single(&self, word: TokenWord) -> Result<Vec<Arc<TokenMatrix>>, String> {
-        if self.grid_size == 0 {
-            return Ok(vec![]);
-        }
-        let mut solution_set = vec![];
-        let mut solution_matrix = TokenMatrix::new(self.grid_size);
-        solution_matrix.push(word).unwrap();
+        self.symmetric_words_single_with(word, SearchMode::All)
+    }
 
-        fn backtrack(
-            prefix_map: &PrefixMap,
-            solution_matrix: &mut TokenMatrix,
-            solution_set: &mut Vec<Arc<TokenMatrix>>,
-        ) {
-            if solution_matrix.len() == 0 {
-                return;
-            }
-            if solution_matrix.is_full() {
-                if solution_matrix.is_symmetric() {
-                    solution_set.push(Arc::new(solution_matrix.clone()));
-                }
-                return;
-            }
-            let next_prefix = parser::next_prefix(solution_matrix);
+    /// Takes the first word of a matrix and returns the solutions with that word
+    /// in the first row that the search mode asks for.
+    #[inline]
+    fn symmetric_words_single_with(
+        &self,
+        word: TokenWord,
+        mode: SearchMode,
+    ) -> Result<Vec<Arc<TokenMatrix>>, String> {
+        self.search_single(word, mode, None)
+            .map(|(solution_set, _)| solution_set)
+    }
 
-            let prefixed_words = if prefix_map.use_table {
-                prefix_map.get_prefix_words_table(&next_prefix)
-            } else {
-                prefix_map.get_prefix_words(&next_prefix)
-            };
+    /// Takes the first word of a matrix and counts all possible solutions with
+    /// that word in the first row without collecting them.
+    #[inline]
+    fn count_symmetric_words_single(&self, word: TokenWord) -> Result<usize, String> {
+        self.count_single(word, None)
+            .map(|(solution_count, _)| solution_count)
+    }
 
-            for word in prefixed_words {
-                solution_matrix.push((*word).clone()).unwrap();
-                backtrack(prefix_map, solution_matrix, solution_set);
-                solution_matrix.pop();
-            }
-        }
+    /// Same as `symmetric_words_single_with`, but stops once `deadline` has passed.
+    /// Returns the solutions found so far and whether the search finished in time.
+    #[inline]
+    fn symmetric_words_single_until(
+        &self,
+        word: TokenWord,
+        mode: SearchMode,
+        deadline: Instant,
+    ) -> Result<(Vec<Arc<TokenMatrix>>, bool), String> {
+        self.search_single(word, mode, Some(deadline))
+    }
 
-        backtrack(self, &mut solution_matrix, &mut solution_set);
-        Ok(solution_set)
+    /// Same as `count_symmetric_words_single`, but stops once `deadline` has passed.
+    /// Returns the solutions counted so far and whether the search finished in time.
+    #[inline]
+    fn count_symmetric_words_single_until(
+        &self,
+        word: TokenWord,
+        deadline: Instant,
+    ) -> Result<(usize, bool), String> {
+        self.count_single(word, Some(deadline))
     }
 }
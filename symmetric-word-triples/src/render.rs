@@ -0,0 +1,379 @@
+//! Renders solutions as chunk grids, the way the README shows them, instead of one line of rows.
+
+use crate::parser::matrix::{BlockedTokenMatrix, TokenMatrix};
+use crate::parser::token::Tokens;
+use crate::parser::wordfilter::NearMiss;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+use std::str::FromStr;
+
+const SVG_CELL_HEIGHT: usize = 32;
+const SVG_CHAR_WIDTH: usize = 10;
+const SVG_GAP: usize = 16;
+
+/// How solutions are written.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RenderStyle {
+    /// The rows on one line, separated by spaces.
+    #[default]
+    Line,
+    /// The chunk grid with aligned columns.
+    Text,
+    /// An HTML table per solution.
+    Html,
+    /// The chunk grid as SVG, with the cells of the diagonal highlighted.
+    Svg,
+}
+
+impl FromStr for RenderStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<RenderStyle, String> {
+        match s {
+            "line" => Ok(RenderStyle::Line),
+            "text" => Ok(RenderStyle::Text),
+            "html" => Ok(RenderStyle::Html),
+            "svg" => Ok(RenderStyle::Svg),
+            _ => Err(format!(
+                "Unknown render style \"{s}\", expected line, text, html or svg."
+            )),
+        }
+    }
+}
+
+impl RenderStyle {
+    /// The end of the output file names, `.txt` for lines so they stay readable for
+    /// `verify` and `merge`.
+    pub fn file_suffix(&self) -> &'static str {
+        match self {
+            RenderStyle::Line => ".txt",
+            RenderStyle::Text => "_text.txt",
+            RenderStyle::Html => ".html",
+            RenderStyle::Svg => ".svg",
+        }
+    }
+
+    /// Renders a single solution.
+    pub fn render(&self, tkn_matrix: &TokenMatrix, tokens: &Tokens) -> String {
+        self.render_grid(&chunk_rows(tkn_matrix, tokens))
+    }
+
+    /// Renders a single grid with blocked cells. Blocked cells are `#` in lines and text,
+    /// and black in HTML and SVG.
+    pub fn render_blocked(&self, matrix: &BlockedTokenMatrix, tokens: &Tokens) -> String {
+        self.render_grid(&blocked_chunk_rows(matrix, tokens))
+    }
+
+    /// Renders solutions into one document: a line per solution, text grids separated by
+    /// empty lines, an HTML page with a table per solution, or one SVG with the grids below
+    /// each other.
+    pub fn render_document<'a, I>(&self, solutions: I, tokens: &Tokens) -> String
+    where
+        I: IntoIterator<Item = &'a TokenMatrix>,
+    {
+        self.document(
+            solutions
+                .into_iter()
+                .map(|tkn_matrix| chunk_rows(tkn_matrix, tokens)),
+        )
+    }
+
+    /// Renders near misses into one document like `render_document`. Lines end with the
+    /// mismatched cells, `| (row,column) ...` counted from 1. Text marks the chunks of
+    /// mismatched cells with `*`, HTML gives them the class `mismatch` and SVG colors them.
+    pub fn render_near_miss_document<'a, I>(&self, near_misses: I, tokens: &Tokens) -> String
+    where
+        I: IntoIterator<Item = &'a NearMiss>,
+    {
+        let grids = near_misses.into_iter().map(|near_miss| Grid {
+            mismatches: near_miss.mismatches.clone(),
+            ..chunk_rows(&near_miss.square, tokens)
+        });
+        if *self != RenderStyle::Line {
+            return self.document(grids);
+        }
+        let mut document = String::new();
+        for grid in grids {
+            let cells = grid
+                .mismatches
+                .iter()
+                .map(|(row, column)| format!(" ({},{})", row + 1, column + 1))
+                .collect::<String>();
+            writeln!(document, "{} |{cells}", line(&grid)).unwrap();
+        }
+        document
+    }
+
+    /// Same as `render_document` for grids with blocked cells.
+    pub fn render_blocked_document<'a, I>(&self, solutions: I, tokens: &Tokens) -> String
+    where
+        I: IntoIterator<Item = &'a BlockedTokenMatrix>,
+    {
+        self.document(
+            solutions
+                .into_iter()
+                .map(|matrix| blocked_chunk_rows(matrix, tokens)),
+        )
+    }
+
+    fn render_grid(&self, grid: &Grid) -> String {
+        match self {
+            RenderStyle::Line => line(grid),
+            RenderStyle::Text => text(grid),
+            RenderStyle::Html => html(grid),
+            RenderStyle::Svg => {
+                let mut cells = String::new();
+                let (width, height) = svg_cells(grid, &mut cells);
+                svg_element(width, height, &cells)
+            }
+        }
+    }
+
+    fn document(&self, grids: impl Iterator<Item = Grid>) -> String {
+        let mut document = String::new();
+        match self {
+            RenderStyle::Line => {
+                for grid in grids {
+                    writeln!(document, "{}", line(&grid)).unwrap();
+                }
+            }
+            RenderStyle::Text => {
+                for (i, grid) in grids.enumerate() {
+                    if i > 0 {
+                        document.push('\n');
+                    }
+                    document.push_str(&text(&grid));
+                }
+            }
+            RenderStyle::Html => {
+                document.push_str(concat!(
+                    "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n",
+                    "<style>table.square { border-collapse: collapse; margin: 1em 0; font-family: monospace; }\n",
+                    "table.square td { border: 1px solid #444; padding: 0.2em 0.5em; }\n",
+                    "table.square td.diagonal { background: #fde68a; }\n",
+                    "table.square td.blocked { background: #222222; }\n",
+                    "table.square td.mismatch { background: #fca5a5; }</style>\n",
+                    "</head>\n<body>\n",
+                ));
+                for grid in grids {
+                    document.push_str(&html(&grid));
+                }
+                document.push_str("</body>\n</html>\n");
+            }
+            RenderStyle::Svg => {
+                let (mut width, mut height) = (0, 0);
+                let mut cells = String::new();
+                for grid in grids {
+                    if height > 0 {
+                        height += SVG_GAP;
+                    }
+                    writeln!(cells, "<g transform=\"translate(0,{height})\">").unwrap();
+                    let (grid_width, grid_height) = svg_cells(&grid, &mut cells);
+                    cells.push_str("</g>\n");
+                    width = width.max(grid_width);
+                    height += grid_height;
+                }
+                document.push_str(&svg_element(width, height, &cells));
+            }
+        }
+        document
+    }
+}
+
+/// The chunks of every row, `None` for blocked cells.
+struct Grid {
+    rows: Vec<Vec<Option<String>>>,
+    /// The cells `(row, column)` above the diagonal that differ from their mirror cell.
+    mismatches: Vec<(usize, usize)>,
+}
+
+impl Grid {
+    fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// Whether the cell or its mirror cell is a mismatch.
+    fn is_mismatch(&self, i: usize, j: usize) -> bool {
+        self.mismatches.contains(&(i.min(j), i.max(j)))
+    }
+}
+
+fn chunk_rows(tkn_matrix: &TokenMatrix, tokens: &Tokens) -> Grid {
+    Grid {
+        rows: tkn_matrix
+            .rows()
+            .map(|row| row.iter().map(|tkn| tokens.stringify_token(*tkn)).collect())
+            .collect(),
+        mismatches: vec![],
+    }
+}
+
+fn blocked_chunk_rows(matrix: &BlockedTokenMatrix, tokens: &Tokens) -> Grid {
+    Grid {
+        rows: matrix
+            .rows()
+            .map(|row| {
+                row.iter()
+                    .map(|cell| cell.and_then(|tkn| tokens.stringify_token(tkn)))
+                    .collect()
+            })
+            .collect(),
+        mismatches: vec![],
+    }
+}
+
+/// Number of characters of the widest chunk.
+fn chunk_width(grid: &Grid) -> usize {
+    grid.rows
+        .iter()
+        .flatten()
+        .flatten()
+        .map(|chunk| chunk.chars().count())
+        .max()
+        .unwrap_or(0)
+}
+
+/// The rows on one line, separated by spaces, like the output files of the search.
+pub fn render_line(tkn_matrix: &TokenMatrix, tokens: &Tokens) -> String {
+    line(&chunk_rows(tkn_matrix, tokens))
+}
+
+/// The chunk grid, a line per row with the columns padded to the same width.
+pub fn render_text(tkn_matrix: &TokenMatrix, tokens: &Tokens) -> String {
+    text(&chunk_rows(tkn_matrix, tokens))
+}
+
+/// A table with a cell per chunk. The cells of the diagonal have the class `diagonal`.
+pub fn render_html(tkn_matrix: &TokenMatrix, tokens: &Tokens) -> String {
+    html(&chunk_rows(tkn_matrix, tokens))
+}
+
+/// The chunk grid as a standalone SVG image, with the cells of the diagonal highlighted.
+pub fn render_svg(tkn_matrix: &TokenMatrix, tokens: &Tokens) -> String {
+    RenderStyle::Svg.render(tkn_matrix, tokens)
+}
+
+fn line(grid: &Grid) -> String {
+    let blocked = "#".repeat(chunk_width(grid));
+    grid.rows
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|cell| cell.as_deref().unwrap_or(&blocked))
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn text(grid: &Grid) -> String {
+    // Mismatched chunks get a `*` after them.
+    let cell_text = |i: usize, j: usize, chunk: &str| {
+        if grid.is_mismatch(i, j) {
+            format!("{chunk}*")
+        } else {
+            chunk.to_string()
+        }
+    };
+    let widths = (0..grid.len())
+        .map(|j| {
+            grid.rows
+                .iter()
+                .enumerate()
+                .filter_map(|(i, row)| Some((i, row.get(j)?)))
+                .map(|(i, cell)| {
+                    cell.as_ref()
+                        .map_or(1, |chunk| cell_text(i, j, chunk).chars().count())
+                })
+                .max()
+                .unwrap_or(0)
+        })
+        .collect::<Vec<_>>();
+    let mut text = String::new();
+    for (i, row) in grid.rows.iter().enumerate() {
+        let line = row
+            .iter()
+            .zip(&widths)
+            .enumerate()
+            .map(|(j, (cell, &width))| match cell {
+                Some(chunk) => format!("{:<width$}", cell_text(i, j, chunk)),
+                None => "#".repeat(width),
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        writeln!(text, "{}", line.trim_end()).unwrap();
+    }
+    text
+}
+
+fn html(grid: &Grid) -> String {
+    let mut html = String::from("<table class=\"square\">\n");
+    for (i, row) in grid.rows.iter().enumerate() {
+        html.push_str("  <tr>");
+        for (j, cell) in row.iter().enumerate() {
+            match cell {
+                Some(chunk) => {
+                    let class = if grid.is_mismatch(i, j) {
+                        " class=\"mismatch\""
+                    } else if i == j {
+                        " class=\"diagonal\""
+                    } else {
+                        ""
+                    };
+                    write!(html, "<td{class}>{}</td>", xml_escape(chunk)).unwrap();
+                }
+                None => html.push_str("<td class=\"blocked\"></td>"),
+            }
+        }
+        html.push_str("</tr>\n");
+    }
+    html.push_str("</table>\n");
+    html
+}
+
+/// Writes the cells of a grid and returns its width and height.
+fn svg_cells(grid: &Grid, svg: &mut String) -> (usize, usize) {
+    let cell_width = chunk_width(grid) * SVG_CHAR_WIDTH + SVG_GAP;
+    for (i, row) in grid.rows.iter().enumerate() {
+        for (j, cell) in row.iter().enumerate() {
+            let (x, y) = (j * cell_width, i * SVG_CELL_HEIGHT);
+            let fill = match cell {
+                None => "#222222",
+                Some(_) if grid.is_mismatch(i, j) => "#fca5a5",
+                Some(_) if i == j => "#fde68a",
+                Some(_) => "#ffffff",
+            };
+            writeln!(
+                svg,
+                "<rect x=\"{x}\" y=\"{y}\" width=\"{cell_width}\" height=\"{SVG_CELL_HEIGHT}\" fill=\"{fill}\" stroke=\"#444444\"/>"
+            )
+            .unwrap();
+            if let Some(chunk) = cell {
+                writeln!(
+                    svg,
+                    "<text x=\"{}\" y=\"{}\" text-anchor=\"middle\" dominant-baseline=\"central\">{}</text>",
+                    x + cell_width / 2,
+                    y + SVG_CELL_HEIGHT / 2,
+                    xml_escape(chunk)
+                )
+                .unwrap();
+            }
+        }
+    }
+    (grid.len() * cell_width, grid.len() * SVG_CELL_HEIGHT)
+}
+
+fn svg_element(width: usize, height: usize, content: &str) -> String {
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" viewBox=\"0 0 {width} {height}\" font-family=\"monospace\" font-size=\"16\">\n{content}</svg>\n"
+    )
+}
+
+pub(crate) fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
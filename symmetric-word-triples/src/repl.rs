@@ -0,0 +1,260 @@
+use crate::parser::{
+    self,
+    matrix::TokenMatrix,
+    memo::MemoStrategy,
+    wordfilter::{PrefixMap, SearchMode, WordDict, WordFilter},
+};
+use crate::render::RenderStyle;
+use std::io::{BufRead, Write};
+use std::sync::Arc;
+
+const HELP: &str = "\
+Commands:
+  grid <n>             Set the grid size.
+  chunk <n>            Set the chunk size.
+  info                 Show the dictionary and the current settings.
+  seed <word>          Find all squares with <word> in the first row.
+  count <word>         Count the squares with <word> in the first row.
+  any <word> [word..]  Find all squares with all of the words in any rows.
+  prefix [chunks]      List the words starting with the given chunks.
+  tokens [text]        List the tokens, optionally only those containing <text>.
+  next | prev          Show the next or previous page of the last result.
+  page <n>             Set the number of lines per page.
+  render <style>       Show squares as line, text, html or svg.
+  help                 Show this help.
+  quit | exit          Leave the shell.";
+
+/// Interactive shell over a dictionary that is loaded once.
+/// The prefix map is rebuilt lazily after the grid or chunk size changes.
+pub struct Repl {
+    word_dictionary: WordDict,
+    grid_size: usize,
+    chunk_size: usize,
+    prefix_map: Option<PrefixMap>,
+    lines: Vec<String>,
+    page: usize,
+    page_size: usize,
+    render: RenderStyle,
+}
+
+impl Repl {
+    pub fn new(word_dictionary: WordDict, grid_size: usize, chunk_size: usize) -> Repl {
+        Repl {
+            word_dictionary,
+            grid_size,
+            chunk_size,
+            prefix_map: None,
+            lines: vec![],
+            page: 0,
+            page_size: 20,
+            render: RenderStyle::Line,
+        }
+    }
+
+    /// Reads commands from `input` until it ends or `quit` is entered.
+    pub fn run<R: BufRead, W: Write>(&mut self, input: R, mut output: W) -> std::io::Result<()> {
+        write!(output, "> ")?;
+        output.flush()?;
+        for line in input.lines() {
+            let line = line?;
+            let command = line.trim();
+            if command == "quit" || command == "exit" {
+                break;
+            }
+            match self.execute(command) {
+                Ok(text) if text.is_empty() => {}
+                Ok(text) => writeln!(output, "{text}")?,
+                Err(error) => writeln!(output, "Error: {error}")?,
+            }
+            write!(output, "> ")?;
+            output.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Executes a single command and returns the text to show.
+    pub fn execute(&mut self, line: &str) -> Result<String, String> {
+        let mut parts = line.split_whitespace();
+        let Some(command) = parts.next() else {
+            return Ok(String::new());
+        };
+        let argument = parts.next();
+
+        match command {
+            "help" => Ok(HELP.to_string()),
+            "grid" => {
+                self.grid_size = parse_size(argument, "grid")?;
+                self.prefix_map = None;
+                Ok(format!("Grid size: {}", self.grid_size))
+            }
+            "chunk" => {
+                self.chunk_size = parse_size(argument, "chunk")?;
+                self.prefix_map = None;
+                Ok(format!("Chunk size: {}", self.chunk_size))
+            }
+            "page" => {
+                self.page_size = parse_size(argument, "page")?;
+                self.page = 0;
+                Ok(self.show_page())
+            }
+            "render" => {
+                self.render = argument
+                    .ok_or("Usage: render line|text|html|svg")?
+                    .parse()?;
+                Ok(format!("Render style: {:?}", self.render))
+            }
+            "info" => {
+                let prefix_map = self.prefix_map();
+                let words = prefix_map.get_prefix_words(&Default::default()).len();
+                let pruned = prefix_map.pruned_words();
+                let tokens = prefix_map.tokens().len();
+                Ok(format!(
+                    "Dictionary: {} words, {} of length {}, {pruned} of them pruned\nGrid size: {}, chunk size: {}, {tokens} tokens",
+                    self.word_dictionary.len(),
+                    words + pruned,
+                    self.grid_size * self.chunk_size,
+                    self.grid_size,
+                    self.chunk_size,
+                ))
+            }
+            "seed" => {
+                let word = argument.ok_or("Usage: seed <word>")?;
+                let prefix_map = self.prefix_map();
+                let word = prefix_map
+                    .try_tokenize_word(word)
+                    .filter(|tkn_word| prefix_map.contains(tkn_word))
+                    .ok_or_else(|| format!("\"{word}\" is not a word of the current size."))?;
+                let solution_set = prefix_map.symmetric_words_single(word)?;
+                let lines = self.render_solutions(&solution_set);
+                Ok(self.show_lines(lines))
+            }
+            "any" => {
+                let first = argument.ok_or("Usage: any <word> [word ...]")?;
+                let prefix_map = self.prefix_map();
+                let words = [first]
+                    .into_iter()
+                    .chain(parts)
+                    .map(|word| {
+                        prefix_map
+                            .try_tokenize_word(word)
+                            .filter(|tkn_word| prefix_map.contains(tkn_word))
+                            .ok_or_else(|| format!("\"{word}\" is not a word of the current size."))
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                let solution_set =
+                    prefix_map.symmetric_words_containing_all(&words, SearchMode::All)?;
+                let lines = self.render_solutions(&solution_set);
+                Ok(self.show_lines(lines))
+            }
+            "count" => {
+                let word = argument.ok_or("Usage: count <word>")?;
+                let prefix_map = self.prefix_map();
+                let word = prefix_map
+                    .try_tokenize_word(word)
+                    .filter(|tkn_word| prefix_map.contains(tkn_word))
+                    .ok_or_else(|| format!("\"{word}\" is not a word of the current size."))?;
+                let count = prefix_map.count_symmetric_words_single(word)?;
+                Ok(format!("{count} solutions"))
+            }
+            "prefix" => {
+                let prefix = argument.unwrap_or_default();
+                let chunk_size = self.chunk_size;
+                if !prefix.chars().count().is_multiple_of(chunk_size) {
+                    return Err(format!(
+                        "The prefix has to consist of whole chunks of size {chunk_size}."
+                    ));
+                }
+                let prefix_map = self.prefix_map();
+                let lines = match prefix_map.try_tokenize_word(prefix) {
+                    Some(prefix) => prefix_map
+                        .get_prefix_words(&prefix)
+                        .iter()
+                        .map(|word| prefix_map.stringify_token_word(word))
+                        .collect(),
+                    None => vec![],
+                };
+                Ok(self.show_lines(lines))
+            }
+            "tokens" => {
+                let filter = argument.unwrap_or_default();
+                let lines = self
+                    .prefix_map()
+                    .tokens()
+                    .entries()
+                    .into_iter()
+                    .filter(|(_, chunk)| chunk.contains(filter))
+                    .map(|(tkn, chunk)| format!("{:>6} {chunk}", tkn.0))
+                    .collect();
+                Ok(self.show_lines(lines))
+            }
+            "next" => {
+                if (self.page + 1) * self.page_size < self.lines.len() {
+                    self.page += 1;
+                }
+                Ok(self.show_page())
+            }
+            "prev" => {
+                self.page = self.page.saturating_sub(1);
+                Ok(self.show_page())
+            }
+            _ => Err(format!("Unknown command \"{command}\". Try \"help\".")),
+        }
+    }
+
+    /// Returns the prefix map for the current grid and chunk size, building it if needed.
+    fn prefix_map(&mut self) -> &PrefixMap {
+        let (grid_size, chunk_size) = (self.grid_size, self.chunk_size);
+        let word_dictionary = &self.word_dictionary;
+        self.prefix_map.get_or_insert_with(|| {
+            let mut word_dictionary = word_dictionary.clone();
+            parser::len_filter(&mut word_dictionary, grid_size * chunk_size);
+            PrefixMap::new(
+                &word_dictionary,
+                grid_size,
+                chunk_size,
+                MemoStrategy::for_grid_size(grid_size),
+            )
+        })
+    }
+
+    /// Renders every solution as one entry of a result.
+    fn render_solutions(&mut self, solution_set: &[Arc<TokenMatrix>]) -> Vec<String> {
+        let render = self.render;
+        let tokens = self.prefix_map().tokens();
+        solution_set
+            .iter()
+            .map(|solution| render.render(solution, tokens).trim_end().to_string())
+            .collect()
+    }
+
+    /// Stores a new result and shows its first page.
+    fn show_lines(&mut self, lines: Vec<String>) -> String {
+        self.lines = lines;
+        self.page = 0;
+        self.show_page()
+    }
+
+    fn show_page(&self) -> String {
+        if self.lines.is_empty() {
+            return "No results.".to_string();
+        }
+        let pages = self.lines.len().div_ceil(self.page_size);
+        let begin = self.page * self.page_size;
+        let end = (begin + self.page_size).min(self.lines.len());
+        let mut text = self.lines[begin..end].join("\n");
+        text.push_str(&format!(
+            "\n-- page {}/{}, {} results --",
+            self.page + 1,
+            pages,
+            self.lines.len()
+        ));
+        text
+    }
+}
+
+fn parse_size(argument: Option<&str>, command: &str) -> Result<usize, String> {
+    argument
+        .and_then(|n| n.parse().ok())
+        .filter(|&n| n > 0)
+        .ok_or_else(|| format!("Usage: {command} <n> with n > 0"))
+}
@@ -0,0 +1,466 @@
+use crate::parser::{
+    self,
+    matrix::TokenMatrix,
+    memo::MemoStrategy,
+    token::TokenWord,
+    wordfilter::{Hr, PrefixMap, SearchMode, WordDict, WordFilter},
+};
+use crate::render::RenderStyle;
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    path::Path,
+    sync::{Arc, OnceLock},
+    time::{Duration, Instant},
+};
+
+/// Settings of the query server.
+#[derive(Clone, Debug)]
+pub struct ServerConfig {
+    /// Address to listen on. Should stay on localhost, there is no authentication.
+    pub addr: String,
+    /// Time a single request may spend searching before it returns what it found so far.
+    pub timeout: Duration,
+    /// Upper bound for the number of solutions or words in a response.
+    pub max_results: usize,
+    /// Largest grid and chunk size a request may ask for. Every size pair keeps its prefix
+    /// map, so these also bound the memory of the server.
+    pub max_grid_size: usize,
+    pub max_chunk_size: usize,
+    /// Number of threads that handle requests.
+    pub workers: usize,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfig {
+            addr: "127.0.0.1:7878".to_string(),
+            timeout: Duration::from_secs(10),
+            max_results: 1000,
+            max_grid_size: 8,
+            max_chunk_size: 16,
+            workers: std::thread::available_parallelism().map_or(4, |workers| workers.get()),
+        }
+    }
+}
+
+/// Answers JSON queries over dictionaries that are loaded once.
+/// Prefix maps are built on the first request for a grid and chunk size and kept afterwards.
+pub struct QueryServer {
+    config: ServerConfig,
+    dictionaries: BTreeMap<String, WordDict>,
+    /// A cell per dictionary, grid and chunk size, filled by the first request for it.
+    /// The map is only locked to find the cell, not while the prefix map is built.
+    prefix_maps: DashMap<(String, usize, usize), Arc<PrefixMapCell>, Hr>,
+}
+
+/// Holds a prefix map once its first request built it.
+type PrefixMapCell = OnceLock<Arc<PrefixMap>>;
+
+#[derive(Deserialize)]
+struct SeedRequest {
+    dictionary: String,
+    grid_size: usize,
+    chunk_size: usize,
+    word: String,
+    limit: Option<usize>,
+    /// Returns a reproducible random sample instead of the first solutions if set.
+    seed: Option<u64>,
+    /// Also returns every solution rendered in this style.
+    render: Option<RenderStyle>,
+}
+
+#[derive(Deserialize)]
+struct TemplateRequest {
+    dictionary: String,
+    grid_size: usize,
+    chunk_size: usize,
+    /// One pattern per row, `?` matches any character. Missing or empty rows match anything.
+    rows: Vec<String>,
+    limit: Option<usize>,
+    render: Option<RenderStyle>,
+}
+
+#[derive(Deserialize)]
+struct PrefixRequest {
+    dictionary: String,
+    grid_size: usize,
+    chunk_size: usize,
+    prefix: String,
+    limit: Option<usize>,
+}
+
+#[derive(Deserialize)]
+struct CountRequest {
+    dictionary: String,
+    grid_size: usize,
+    chunk_size: usize,
+    /// Counts the solutions of the whole dictionary if not set.
+    word: Option<String>,
+}
+
+#[derive(Serialize)]
+struct SolutionsResponse {
+    solutions: Vec<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rendered: Option<Vec<String>>,
+    timed_out: bool,
+}
+
+#[derive(Serialize)]
+struct PrefixResponse {
+    words: Vec<String>,
+    total: usize,
+}
+
+#[derive(Serialize)]
+struct CountResponse {
+    count: usize,
+    timed_out: bool,
+}
+
+#[derive(Serialize)]
+struct DictionaryInfo {
+    name: String,
+    words: usize,
+}
+
+#[derive(Serialize)]
+struct ErrorResponse {
+    error: String,
+}
+
+impl QueryServer {
+    pub fn new(config: ServerConfig) -> QueryServer {
+        QueryServer {
+            config,
+            dictionaries: BTreeMap::new(),
+            prefix_maps: DashMap::default(),
+        }
+    }
+
+    /// Reads a dictionary file and makes it available under `name`.
+    pub fn load_dictionary(&mut self, name: &str, path: &Path) -> std::io::Result<()> {
+        let mut word_dictionary = vec![];
+        parser::file_vec(path, &mut word_dictionary)?;
+        self.insert_dictionary(name, word_dictionary);
+        Ok(())
+    }
+
+    /// Makes a word list available under `name`, replacing a dictionary of the same name.
+    pub fn insert_dictionary(&mut self, name: &str, word_dictionary: WordDict) {
+        self.prefix_maps
+            .retain(|(dictionary, _, _), _| dictionary != name);
+        self.dictionaries.insert(name.to_string(), word_dictionary);
+    }
+
+    /// Returns the prefix map of a dictionary, building it if it's the first request for
+    /// this grid and chunk size.
+    fn prefix_map(
+        &self,
+        dictionary: &str,
+        grid_size: usize,
+        chunk_size: usize,
+    ) -> Result<Arc<PrefixMap>, String> {
+        if grid_size == 0 || chunk_size == 0 {
+            return Err("Grid size and chunk size have to be at least 1.".to_string());
+        }
+        if grid_size > self.config.max_grid_size || chunk_size > self.config.max_chunk_size {
+            return Err(format!(
+                "Grid size and chunk size can be at most {} and {}.",
+                self.config.max_grid_size, self.config.max_chunk_size
+            ));
+        }
+        let word_len = grid_size
+            .checked_mul(chunk_size)
+            .ok_or("Grid size and chunk size are too large.")?;
+        let word_dictionary = self
+            .dictionaries
+            .get(dictionary)
+            .ok_or_else(|| format!("Unknown dictionary \"{dictionary}\"."))?;
+
+        let key = (dictionary.to_string(), grid_size, chunk_size);
+        let cell = Arc::clone(&self.prefix_maps.entry(key).or_default());
+        // Other requests for the same sizes wait for this build, all others go on.
+        let prefix_map = cell.get_or_init(|| {
+            let mut word_dictionary = word_dictionary.clone();
+            parser::len_filter(&mut word_dictionary, word_len);
+            Arc::new(PrefixMap::new(
+                &word_dictionary,
+                grid_size,
+                chunk_size,
+                MemoStrategy::for_grid_size(grid_size),
+            ))
+        });
+        Ok(Arc::clone(prefix_map))
+    }
+
+    fn limit(&self, requested: Option<usize>) -> usize {
+        requested
+            .unwrap_or(self.config.max_results)
+            .min(self.config.max_results)
+    }
+
+    /// Answers a single request. Returns the HTTP status code and the JSON body.
+    pub fn handle(&self, method: &str, url: &str, body: &str) -> (u16, String) {
+        // Starts before a prefix map is built, so building counts against the timeout too.
+        let deadline = Instant::now() + self.config.timeout;
+        let response = match (method, url) {
+            ("GET", "/dictionaries") => Ok(self.dictionaries_info()),
+            ("POST", "/seed") => parse(body).and_then(|request| self.seed(request, deadline)),
+            ("POST", "/template") => {
+                parse(body).and_then(|request| self.template(request, deadline))
+            }
+            ("POST", "/prefix") => parse(body).and_then(|request| self.prefix(request)),
+            ("POST", "/count") => parse(body).and_then(|request| self.count(request, deadline)),
+            _ => {
+                let error = ErrorResponse {
+                    error: format!("No route for {method} {url}."),
+                };
+                return (404, serde_json::to_string(&error).unwrap());
+            }
+        };
+        match response {
+            Ok(body) => (200, body),
+            Err(error) => (
+                400,
+                serde_json::to_string(&ErrorResponse { error }).unwrap(),
+            ),
+        }
+    }
+
+    fn dictionaries_info(&self) -> String {
+        let info = self
+            .dictionaries
+            .iter()
+            .map(|(name, words)| DictionaryInfo {
+                name: name.clone(),
+                words: words.len(),
+            })
+            .collect::<Vec<_>>();
+        serde_json::to_string(&info).unwrap()
+    }
+
+    fn seed(&self, request: SeedRequest, deadline: Instant) -> Result<String, String> {
+        let prefix_map =
+            self.prefix_map(&request.dictionary, request.grid_size, request.chunk_size)?;
+        let word = known_word(&prefix_map, &request.word)?;
+        let limit = self.limit(request.limit);
+        let mode = match request.seed {
+            Some(seed) => SearchMode::Sample {
+                limit,
+                seed,
+                stream: 0,
+            },
+            None => SearchMode::First { limit },
+        };
+
+        let (solution_set, complete) =
+            prefix_map.symmetric_words_single_until(word, mode, deadline)?;
+        let solutions = solution_set
+            .iter()
+            .map(|solution| prefix_map.stringify_token_rows(solution))
+            .collect();
+        to_json(&SolutionsResponse {
+            solutions,
+            rendered: render_solutions(&prefix_map, &solution_set, request.render),
+            timed_out: !complete,
+        })
+    }
+
+    fn template(&self, request: TemplateRequest, deadline: Instant) -> Result<String, String> {
+        let prefix_map =
+            self.prefix_map(&request.dictionary, request.grid_size, request.chunk_size)?;
+        if request.rows.len() > request.grid_size {
+            return Err(format!(
+                "The template has {} rows, but the grid only has {}.",
+                request.rows.len(),
+                request.grid_size
+            ));
+        }
+        // Like `len_filter`, lengths are in bytes. A `?` matches one character, which can
+        // take more than one byte.
+        let word_len = request.grid_size * request.chunk_size;
+        if let Some(row) = request.rows.iter().find(|row| {
+            !row.is_empty()
+                && (row.len() > word_len || (!row.contains('?') && row.len() < word_len))
+        }) {
+            return Err(format!(
+                "The template row \"{row}\" has to be empty or {word_len} bytes long."
+            ));
+        }
+        let limit = self.limit(request.limit);
+
+        // Rows without `?` are fixed words and constrain the search, the others are only
+        // matched against the solutions. A fixed row with an unknown chunk is no word.
+        let fixed = request
+            .rows
+            .iter()
+            .map(|row| {
+                if row.is_empty() || row.contains('?') {
+                    Some(None)
+                } else {
+                    prefix_map.try_tokenize_word(row).map(Some)
+                }
+            })
+            .collect::<Option<Vec<_>>>();
+        let (solutions, complete) = match fixed {
+            Some(fixed) => prefix_map.symmetric_words_fixed_until(
+                &fixed,
+                SearchMode::First { limit },
+                deadline,
+                |solution| {
+                    request
+                        .rows
+                        .iter()
+                        .zip(prefix_map.stringify_token_rows(solution))
+                        .all(|(pattern, row)| matches_pattern(pattern, &row))
+                },
+            )?,
+            None => (vec![], true),
+        };
+        to_json(&SolutionsResponse {
+            solutions: solutions
+                .iter()
+                .map(|solution| prefix_map.stringify_token_rows(solution))
+                .collect(),
+            rendered: render_solutions(&prefix_map, &solutions, request.render),
+            timed_out: !complete,
+        })
+    }
+
+    fn prefix(&self, request: PrefixRequest) -> Result<String, String> {
+        let prefix_map =
+            self.prefix_map(&request.dictionary, request.grid_size, request.chunk_size)?;
+        if !request
+            .prefix
+            .chars()
+            .count()
+            .is_multiple_of(request.chunk_size)
+        {
+            return Err(format!(
+                "The prefix \"{}\" has to consist of whole chunks of size {}.",
+                request.prefix, request.chunk_size
+            ));
+        }
+        let limit = self.limit(request.limit);
+        let words = match prefix_map.try_tokenize_word(&request.prefix) {
+            Some(prefix) => prefix_map.get_prefix_words(&prefix),
+            None => vec![],
+        };
+        to_json(&PrefixResponse {
+            total: words.len(),
+            words: words
+                .iter()
+                .take(limit)
+                .map(|word| prefix_map.stringify_token_word(word))
+                .collect(),
+        })
+    }
+
+    fn count(&self, request: CountRequest, deadline: Instant) -> Result<String, String> {
+        let prefix_map =
+            self.prefix_map(&request.dictionary, request.grid_size, request.chunk_size)?;
+        let seeds = match &request.word {
+            Some(word) => vec![known_word(&prefix_map, word)?],
+            None => prefix_map
+                .get_prefix_words(&TokenWord::new())
+                .iter()
+                .map(|word| (**word).clone())
+                .collect(),
+        };
+
+        let mut count = 0;
+        let mut timed_out = false;
+        for seed in seeds {
+            let (solution_count, complete) =
+                prefix_map.count_symmetric_words_single_until(seed, deadline)?;
+            count += solution_count;
+            if !complete {
+                timed_out = true;
+                break;
+            }
+        }
+        to_json(&CountResponse { count, timed_out })
+    }
+
+    /// Listens on the configured address and answers requests until the process is stopped.
+    /// Requests are handled by a pool of `workers` threads, the others wait in line.
+    pub fn serve(self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let server = tiny_http::Server::http(&self.config.addr)?;
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(self.config.workers)
+            .build()?;
+        println!("Listening on http://{}", self.config.addr);
+        let query_server = Arc::new(self);
+        for mut request in server.incoming_requests() {
+            let query_server = Arc::clone(&query_server);
+            pool.spawn(move || {
+                let mut body = String::new();
+                let (status, body) = match request.as_reader().read_to_string(&mut body) {
+                    Ok(_) => query_server.handle(request.method().as_str(), request.url(), &body),
+                    Err(error) => (
+                        400,
+                        serde_json::to_string(&ErrorResponse {
+                            error: error.to_string(),
+                        })
+                        .unwrap(),
+                    ),
+                };
+                let header = tiny_http::Header::from_bytes("Content-Type", "application/json")
+                    .expect("Valid header.");
+                let response = tiny_http::Response::from_string(body)
+                    .with_status_code(status)
+                    .with_header(header);
+                request.respond(response).ok();
+            });
+        }
+        Ok(())
+    }
+}
+
+fn render_solutions(
+    prefix_map: &PrefixMap,
+    solution_set: &[Arc<TokenMatrix>],
+    render: Option<RenderStyle>,
+) -> Option<Vec<String>> {
+    render.map(|render| {
+        solution_set
+            .iter()
+            .map(|solution| render.render(solution, prefix_map.tokens()))
+            .collect()
+    })
+}
+
+fn parse<'a, T: Deserialize<'a>>(body: &'a str) -> Result<T, String> {
+    serde_json::from_str(body).map_err(|error| format!("Invalid request: {error}"))
+}
+
+fn to_json<T: Serialize>(value: &T) -> Result<String, String> {
+    serde_json::to_string(value).map_err(|error| error.to_string())
+}
+
+/// Tokenizes a word and checks that it is in the dictionary.
+fn known_word(prefix_map: &PrefixMap, word: &str) -> Result<TokenWord, String> {
+    prefix_map
+        .try_tokenize_word(word)
+        .filter(|tkn_word| prefix_map.contains(tkn_word))
+        .ok_or_else(|| {
+            format!(
+                "\"{word}\" is not a word of length {} in the dictionary.",
+                prefix_map.grid_size() * prefix_map.chunk_size()
+            )
+        })
+}
+
+/// Checks if the word matches the pattern. `?` matches any character and an empty
+/// pattern matches every word.
+fn matches_pattern(pattern: &str, word: &str) -> bool {
+    pattern.is_empty()
+        || (pattern.chars().count() == word.chars().count()
+            && pattern
+                .chars()
+                .zip(word.chars())
+                .all(|(p, c)| p == '?' || p == c))
+}
@@ -0,0 +1,367 @@
+//! Searches split into shards of the seed words, so separate processes or machines can each
+//! run one shard of a combination. `merge_shards` combines the outputs of all shards into
+//! the same file a single run writes.
+
+use crate::files::symmetric_words_of_seeds;
+use crate::manifest::ENGINE_VERSION;
+use crate::parser;
+use crate::parser::index::{self, fnv1a};
+use crate::parser::matrix::TokenMatrix;
+use crate::parser::memo::MemoStrategy;
+use crate::parser::token::{TokenWord, Tokens};
+use crate::parser::wordfilter::{PrefixMap, SearchMode};
+use crate::render::RenderStyle;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// How the seed words are split into shards. Both only depend on the seed words,
+/// so every machine computes the same shards.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ShardBy {
+    /// Consecutive ranges of the sorted seed words.
+    Range,
+    /// The FNV-1a hash of the seed word modulo the number of shards.
+    /// Spreads seeds with many solutions, which often share a start, over the shards.
+    Hash,
+}
+
+impl FromStr for ShardBy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<ShardBy, String> {
+        match s {
+            "range" => Ok(ShardBy::Range),
+            "hash" => Ok(ShardBy::Hash),
+            _ => Err(format!(
+                "Unknown shard strategy \"{s}\", expected range or hash."
+            )),
+        }
+    }
+}
+
+/// One of `count` shards of a search, numbered from 0.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Shard {
+    pub index: usize,
+    pub count: usize,
+    pub by: ShardBy,
+}
+
+impl Shard {
+    pub fn new(index: usize, count: usize, by: ShardBy) -> Result<Shard, String> {
+        if index >= count {
+            return Err(format!(
+                "Shard {index} doesn't exist, there are {count} shards."
+            ));
+        }
+        Ok(Shard { index, count, by })
+    }
+
+    /// Parses `index/count`, like `3/8`.
+    pub fn parse(s: &str, by: ShardBy) -> Result<Shard, String> {
+        let invalid = || format!("Invalid shard \"{s}\", expected index/count.");
+        let (index, count) = s.split_once('/').ok_or_else(invalid)?;
+        let index = index.parse().map_err(|_| invalid())?;
+        let count = count.parse().map_err(|_| invalid())?;
+        Shard::new(index, count, by)
+    }
+
+    /// The seed words of this shard. `seeds` are the stringified seed words, sorted.
+    pub fn select<'a>(&self, seeds: &'a [String]) -> Vec<&'a String> {
+        match self.by {
+            ShardBy::Range => {
+                let start = seeds.len() * self.index / self.count;
+                let end = seeds.len() * (self.index + 1) / self.count;
+                seeds[start..end].iter().collect()
+            }
+            ShardBy::Hash => seeds
+                .iter()
+                .filter(|seed| fnv1a(seed.bytes()) % self.count as u64 == self.index as u64)
+                .collect(),
+        }
+    }
+}
+
+/// Written next to the output of a shard once the shard is complete.
+/// `merge_shards` checks that the reports of all shards are there and agree.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShardReport {
+    pub input: PathBuf,
+    /// `dictionary_checksum` of the input file.
+    pub input_checksum: u64,
+    pub grid_size: usize,
+    pub chunk_size: usize,
+    pub shard: usize,
+    pub shard_count: usize,
+    pub by: ShardBy,
+    pub engine_version: String,
+    /// Seed words of this shard and of all shards together.
+    pub seeds: usize,
+    pub total_seeds: usize,
+    pub solutions: usize,
+    /// The output file, relative to the report.
+    pub output: String,
+    /// The output rendered in another style than lines, relative to the report.
+    #[serde(default)]
+    pub rendered: Option<String>,
+}
+
+/// The output and report file names of a shard.
+fn shard_files(name: &str, grid_size: usize, chunk_size: usize, shard: &Shard) -> (String, String) {
+    let stem = format!(
+        "{name}_grid{grid_size}_chunk{chunk_size}_shard{}of{}",
+        shard.index, shard.count
+    );
+    (format!("{stem}.txt"), format!("{stem}.json"))
+}
+
+/// The shard index and count of a report file name written by `run_shard`,
+/// `{stem}_shard{index}of{count}.json`.
+fn parse_report_name(name: &str) -> Option<(usize, usize)> {
+    let (_, shard) = name.strip_suffix(".json")?.rsplit_once("_shard")?;
+    let (index, count) = shard.split_once("of")?;
+    let is_number = |s: &str| !s.is_empty() && s.bytes().all(|byte| byte.is_ascii_digit());
+    if !is_number(index) || !is_number(count) {
+        return None;
+    }
+    Some((index.parse().ok()?, count.parse().ok()?))
+}
+
+/// Renders sorted solution lines, which are kept as they are in the `Line` style.
+fn render_lines(
+    lines: &[String],
+    grid_size: usize,
+    chunk_size: usize,
+    render: RenderStyle,
+) -> Result<String, String> {
+    if render == RenderStyle::Line {
+        return Ok(lines.iter().map(|line| format!("{line}\n")).collect());
+    }
+    let mut tokens = Tokens::new();
+    let mut squares = vec![];
+    for line in lines {
+        let mut square = TokenMatrix::new(grid_size);
+        for row in line.split(' ') {
+            let tkn_word = parser::chunkify(row, chunk_size)
+                .into_iter()
+                .map(|chunk| tokens.insert(chunk))
+                .collect();
+            square.push(tkn_word)?;
+        }
+        squares.push(square);
+    }
+    Ok(render.render_document(&squares, &tokens))
+}
+
+/// Searches one shard of a dictionary and writes its sorted solutions, one per line, and its
+/// report into `output_dir`. The report is written last, so it only exists for complete shards.
+/// With another `render` style than lines the solutions are also written in that style, the
+/// lines stay what `merge_shards` reads.
+pub fn run_shard(
+    dictionary_file: &Path,
+    output_dir: &Path,
+    grid_size: usize,
+    chunk_size: usize,
+    shard: Shard,
+    render: RenderStyle,
+) -> Result<ShardReport, Box<dyn std::error::Error>> {
+    let mut word_dictionary = vec![];
+    parser::file_vec(dictionary_file, &mut word_dictionary)?;
+    let input_checksum = index::dictionary_checksum(&word_dictionary);
+    parser::len_filter(&mut word_dictionary, grid_size * chunk_size);
+    let prefix_map = PrefixMap::new(
+        &word_dictionary,
+        grid_size,
+        chunk_size,
+        MemoStrategy::for_grid_size(grid_size),
+    );
+
+    // Sorted by their strings, so the shards don't depend on the token numbers.
+    let mut all_seeds = prefix_map
+        .get_prefix_words(&TokenWord::new())
+        .iter()
+        .map(|seed| prefix_map.stringify_token_word(seed))
+        .collect::<Vec<_>>();
+    all_seeds.sort_unstable();
+    all_seeds.dedup();
+    let seeds = shard
+        .select(&all_seeds)
+        .into_iter()
+        .map(|seed| Arc::new(prefix_map.tokenize_word(seed)))
+        .collect::<Vec<_>>();
+
+    let mut solutions = symmetric_words_of_seeds(&prefix_map, &seeds, SearchMode::All);
+    solutions.sort_unstable();
+
+    let name = dictionary_file
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().replace(' ', "_"))
+        .unwrap_or_default();
+    let (output, report_file) = shard_files(&name, grid_size, chunk_size, &shard);
+    let report_path = output_dir.join(report_file);
+    let rendered = (render != RenderStyle::Line).then(|| {
+        format!(
+            "{}{}",
+            output.trim_end_matches(".txt"),
+            render.file_suffix()
+        )
+    });
+    std::fs::create_dir_all(output_dir)?;
+    // A report of an earlier run of the shard would vouch for a half written output.
+    std::fs::remove_file(&report_path).ok();
+    let mut file = BufWriter::new(File::create(output_dir.join(&output))?);
+    for solution in &solutions {
+        writeln!(file, "{solution}")?;
+    }
+    file.flush()?;
+    if let Some(rendered) = &rendered {
+        std::fs::write(
+            output_dir.join(rendered),
+            render_lines(&solutions, grid_size, chunk_size, render)?,
+        )?;
+    }
+
+    let report = ShardReport {
+        input: dictionary_file.to_path_buf(),
+        input_checksum,
+        grid_size,
+        chunk_size,
+        shard: shard.index,
+        shard_count: shard.count,
+        by: shard.by,
+        engine_version: ENGINE_VERSION.to_string(),
+        seeds: seeds.len(),
+        total_seeds: all_seeds.len(),
+        solutions: solutions.len(),
+        output,
+        rendered,
+    };
+    let tmp_path = report_path.with_extension("json.tmp");
+    std::fs::write(&tmp_path, serde_json::to_string_pretty(&report)?)?;
+    std::fs::rename(&tmp_path, &report_path)?;
+    Ok(report)
+}
+
+/// Combines the outputs of the shards in `shard_dir` into one sorted file, like a single run.
+/// Only the reports named like `run_shard` names them are read, other files are ignored.
+/// Fails without writing anything if a shard is missing or incomplete, or if the shards
+/// belong to different searches. The merged solutions are written in the `render` style.
+/// Returns the number of solutions.
+pub fn merge_shards(
+    shard_dir: &Path,
+    output: &Path,
+    render: RenderStyle,
+) -> Result<usize, Box<dyn std::error::Error>> {
+    let mut reports = vec![];
+    for entry in std::fs::read_dir(shard_dir)? {
+        let path = entry?.path();
+        let Some((index, count)) = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(parse_report_name)
+        else {
+            continue;
+        };
+        let report: ShardReport = serde_json::from_str(&std::fs::read_to_string(&path)?)
+            .map_err(|error| format!("{}: {error}", path.display()))?;
+        if (report.shard, report.shard_count) != (index, count) {
+            return Err(format!(
+                "{} is the report of shard {} of {}.",
+                path.display(),
+                report.shard,
+                report.shard_count
+            )
+            .into());
+        }
+        reports.push(report);
+    }
+    reports.sort_unstable_by_key(|report| report.shard);
+
+    let first = reports.first().ok_or("There are no shard reports.")?;
+    for report in &reports {
+        let same_search = (
+            report.input_checksum,
+            report.grid_size,
+            report.chunk_size,
+            report.shard_count,
+            report.by,
+            &report.engine_version,
+            report.total_seeds,
+        ) == (
+            first.input_checksum,
+            first.grid_size,
+            first.chunk_size,
+            first.shard_count,
+            first.by,
+            &first.engine_version,
+            first.total_seeds,
+        );
+        if !same_search {
+            return Err(format!(
+                "Shard {} of {} is from another search than shard {} of {}.",
+                report.shard, report.shard_count, first.shard, first.shard_count
+            )
+            .into());
+        }
+    }
+    for pair in reports.windows(2) {
+        if pair[0].shard == pair[1].shard {
+            return Err(format!(
+                "Shard {} of {} has more than one report.",
+                pair[0].shard, first.shard_count
+            )
+            .into());
+        }
+    }
+    for (index, report) in reports.iter().enumerate() {
+        if report.shard != index {
+            return Err(format!("Shard {index} of {} is missing.", first.shard_count).into());
+        }
+    }
+    if reports.len() != first.shard_count {
+        return Err(format!(
+            "Shard {} of {} is missing.",
+            reports.len(),
+            first.shard_count
+        )
+        .into());
+    }
+    let seeds = reports.iter().map(|report| report.seeds).sum::<usize>();
+    if seeds != first.total_seeds {
+        return Err(format!(
+            "The shards searched {seeds} seed words instead of {}.",
+            first.total_seeds
+        )
+        .into());
+    }
+
+    let mut solutions = vec![];
+    for report in &reports {
+        let path = shard_dir.join(&report.output);
+        let lines = BufReader::new(File::open(&path)?)
+            .lines()
+            .collect::<Result<Vec<_>, _>>()?;
+        if lines.len() != report.solutions {
+            return Err(format!(
+                "{} has {} solutions instead of {}.",
+                path.display(),
+                lines.len(),
+                report.solutions
+            )
+            .into());
+        }
+        solutions.extend(lines);
+    }
+    solutions.sort_unstable();
+
+    let document = render_lines(&solutions, first.grid_size, first.chunk_size, render)?;
+    let tmp_path = output.with_extension("txt.tmp");
+    std::fs::write(&tmp_path, document)?;
+    std::fs::rename(&tmp_path, output)?;
+    Ok(solutions.len())
+}
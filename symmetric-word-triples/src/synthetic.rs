@@ -0,0 +1,63 @@
+use crate::parser::wordfilter::WordDict;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+/// Parameters of a generated dictionary, for benchmarks and tests that shouldn't depend on
+/// a word list. The same parameters always generate the same words.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SyntheticDictionary {
+    /// The characters the words are made of.
+    pub alphabet: Vec<char>,
+    pub word_count: usize,
+    pub grid_size: usize,
+    pub chunk_size: usize,
+    /// The share of chunks, from 0 to 1, taken from a small pool that all words share.
+    /// More shared chunks give more prefix matches and more squares.
+    pub chunk_overlap: f64,
+    pub seed: u64,
+}
+
+impl Default for SyntheticDictionary {
+    fn default() -> SyntheticDictionary {
+        SyntheticDictionary {
+            alphabet: ('a'..='z').collect(),
+            word_count: 10_000,
+            grid_size: 3,
+            chunk_size: 3,
+            chunk_overlap: 0.5,
+            seed: 0,
+        }
+    }
+}
+
+impl SyntheticDictionary {
+    /// Generates `word_count` words of `grid_size * chunk_size` characters.
+    /// Words can repeat, like in real word lists with duplicates. Panics if the alphabet is empty.
+    pub fn generate(&self) -> WordDict {
+        let mut rng = ChaCha8Rng::seed_from_u64(self.seed);
+        let random_chunk = |rng: &mut ChaCha8Rng| {
+            (0..self.chunk_size)
+                .map(|_| *self.alphabet.choose(rng).unwrap())
+                .collect::<String>()
+        };
+        let pool_size = (self.word_count as f64).sqrt().ceil().max(1.0) as usize;
+        let pool = (0..pool_size)
+            .map(|_| random_chunk(&mut rng))
+            .collect::<Vec<_>>();
+
+        (0..self.word_count)
+            .map(|_| {
+                (0..self.grid_size)
+                    .map(|_| {
+                        if rng.gen_bool(self.chunk_overlap.clamp(0.0, 1.0)) {
+                            pool.choose(&mut rng).unwrap().clone()
+                        } else {
+                            random_chunk(&mut rng)
+                        }
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,130 @@
+//! Checks output files of the search, so they can be handed on with proof that they're correct.
+
+use crate::parser::{self, wordfilter::WordDict};
+use std::collections::HashSet;
+#[cfg(feature = "native")]
+use std::{collections::BTreeSet, path::Path};
+
+/// The outcome of verifying the lines of an output file.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct VerifyReport {
+    pub lines: usize,
+    pub valid: usize,
+    /// Line number (from 1), line and what is wrong with it.
+    pub invalid: Vec<(usize, String, String)>,
+    /// Line number and line of every repetition of an earlier line.
+    pub duplicates: Vec<(usize, String)>,
+    /// Solutions of the search that are not in the file, if the search was run again.
+    pub missing: Option<Vec<String>>,
+}
+
+impl VerifyReport {
+    pub fn is_ok(&self) -> bool {
+        self.invalid.is_empty()
+            && self.duplicates.is_empty()
+            && self.missing.as_ref().is_none_or(Vec::is_empty)
+    }
+}
+
+/// Verifies lines of `grid_size` space separated rows: every row is split with
+/// `parser::chunkify`, and the rows and columns have to be words of the dictionary
+/// and the grid symmetric.
+pub fn verify_solutions(
+    lines: &[String],
+    word_dictionary: &WordDict,
+    grid_size: usize,
+    chunk_size: usize,
+) -> VerifyReport {
+    let words = word_dictionary
+        .iter()
+        .map(String::as_str)
+        .collect::<HashSet<_>>();
+    let mut report = VerifyReport {
+        lines: lines.len(),
+        ..VerifyReport::default()
+    };
+    let mut seen = HashSet::new();
+    for (i, line) in lines.iter().enumerate() {
+        if !seen.insert(line.as_str()) {
+            report.duplicates.push((i + 1, line.clone()));
+            continue;
+        }
+        match check_square(line, &words, grid_size, chunk_size) {
+            Ok(()) => report.valid += 1,
+            Err(reason) => report.invalid.push((i + 1, line.clone(), reason)),
+        }
+    }
+    report
+}
+
+fn check_square(
+    line: &str,
+    words: &HashSet<&str>,
+    grid_size: usize,
+    chunk_size: usize,
+) -> Result<(), String> {
+    let rows = line.split_whitespace().collect::<Vec<_>>();
+    if rows.len() != grid_size {
+        return Err(format!("{} rows instead of {grid_size}", rows.len()));
+    }
+    let mut grid = vec![];
+    for (i, row) in rows.iter().enumerate() {
+        let chunks = parser::chunkify(row, chunk_size);
+        if row.len() != grid_size * chunk_size || chunks.len() != grid_size {
+            return Err(format!(
+                "row {} \"{row}\" doesn't have {grid_size} chunks of size {chunk_size}",
+                i + 1
+            ));
+        }
+        if !words.contains(row) {
+            return Err(format!("row {} \"{row}\" is not a word", i + 1));
+        }
+        grid.push(chunks);
+    }
+    for j in 0..grid_size {
+        let column = grid
+            .iter()
+            .map(|chunks| chunks[j].as_str())
+            .collect::<String>();
+        if !words.contains(column.as_str()) {
+            return Err(format!("column {} \"{column}\" is not a word", j + 1));
+        }
+    }
+    for (i, chunks) in grid.iter().enumerate() {
+        for (j, chunk) in chunks.iter().enumerate().skip(i + 1) {
+            if *chunk != grid[j][i] {
+                return Err(format!("not symmetric at row {}, column {}", i + 1, j + 1));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Verifies an output file against its dictionary. With `rerun`, the search runs again
+/// to find the solutions the file is missing.
+#[cfg(feature = "native")]
+pub fn verify_file(
+    output_file: &Path,
+    dictionary_file: &Path,
+    grid_size: usize,
+    chunk_size: usize,
+    rerun: bool,
+) -> Result<VerifyReport, Box<dyn std::error::Error>> {
+    let lines = std::fs::read_to_string(output_file)?
+        .lines()
+        .map(String::from)
+        .collect::<Vec<_>>();
+    let mut word_dictionary = vec![];
+    parser::file_vec(dictionary_file, &mut word_dictionary)?;
+    let mut report = verify_solutions(&lines, &word_dictionary, grid_size, chunk_size);
+
+    if rerun {
+        let found = lines.iter().map(String::as_str).collect::<HashSet<_>>();
+        let missing = crate::symmetric_words_in_file_mt(dictionary_file, grid_size, chunk_size)?
+            .into_iter()
+            .filter(|solution| !found.contains(solution.as_str()))
+            .collect::<BTreeSet<_>>();
+        report.missing = Some(missing.into_iter().collect());
+    }
+    Ok(report)
+}
@@ -0,0 +1,124 @@
+use proptest::prelude::*;
+use symmetric_word_triples::parser::{
+    self, ascii,
+    memo::MemoStrategy,
+    token::{TokenWord, Tokens},
+    wordfilter::{PrefixMap, WordDict, WordFilter},
+};
+
+/// The general path: a `String` per chunk, interned with `Tokens::insert`.
+fn tokenize_general(
+    dict: &WordDict,
+    grid_size: usize,
+    chunk_size: usize,
+) -> (Tokens, Vec<TokenWord>) {
+    let mut tokens = Tokens::new();
+    let tkn_words = parser::chunkify_dict(dict, grid_size, chunk_size)
+        .into_iter()
+        .map(|chunky| {
+            chunky
+                .into_iter()
+                .map(|chunk| tokens.insert(chunk))
+                .collect()
+        })
+        .collect();
+    (tokens, tkn_words)
+}
+
+proptest! {
+    #[test]
+    fn ascii_path_matches_general_path(
+        dict in prop::collection::vec("[a-d ~]{0,12}".prop_map(String::from), 0..30),
+        grid_size in 1usize..=4,
+        chunk_size in 1usize..=ascii::MAX_CHUNK_SIZE,
+    ) {
+        let (general_tokens, general_words) = tokenize_general(&dict, grid_size, chunk_size);
+        let mut tokens = Tokens::new();
+        let tkn_words = ascii::tokenize_dict(&dict, grid_size, chunk_size, &mut tokens).unwrap();
+        prop_assert_eq!(tkn_words, general_words);
+        prop_assert_eq!(tokens.entries(), general_tokens.entries());
+    }
+}
+
+#[test]
+fn chunks_of_different_lengths_get_different_tokens() {
+    assert_ne!(ascii::pack(b"a"), ascii::pack(b"a\0"));
+    assert_ne!(ascii::pack(b""), ascii::pack(b"\0"));
+    assert_ne!(ascii::pack(b"abcdefgh"), ascii::pack(b"abcdefgi"));
+}
+
+#[test]
+fn tokenizing_interns_no_strings() {
+    let dict = ["abcd", "cdab", "abab", "abcde"].map(String::from).to_vec();
+    // Only packed chunks and token numbers, there is no string table yet.
+    let packed = ascii::pack_dict(&dict, 2, 2).unwrap();
+    assert_eq!(packed.chunks, [ascii::pack(b"ab"), ascii::pack(b"cd")]);
+    let words = packed
+        .words
+        .iter()
+        .map(|tkn_word| tkn_word.0.iter().map(|tkn| tkn.0).collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    assert_eq!(words, [[0, 1], [1, 0], [0, 0]]);
+
+    let chunks = packed.chunks.iter().map(|&chunk| ascii::unpack(chunk));
+    assert_eq!(chunks.collect::<Vec<_>>(), ["ab", "cd"]);
+    assert_eq!(ascii::unpack(ascii::pack(b"")), "");
+
+    // The string table is only built from the distinct chunks, at the end.
+    let mut tokens = Tokens::new();
+    assert_eq!(
+        ascii::tokenize_dict(&dict, 2, 2, &mut tokens),
+        Some(packed.words)
+    );
+    assert_eq!(tokens.len(), 2);
+    // Tokens that are already there would get other numbers.
+    assert!(ascii::tokenize_dict(&dict, 2, 2, &mut tokens).is_none());
+}
+
+#[test]
+fn other_dictionaries_take_the_general_path() {
+    let dict = vec!["äbcdef".to_string(), "abcdef".to_string()];
+    assert!(!ascii::fits(&dict, 3));
+    assert!(ascii::tokenize_dict(&dict, 2, 3, &mut Tokens::new()).is_none());
+    let ascii_dict = vec!["abcdefghij".to_string()];
+    assert!(ascii::fits(&ascii_dict, 8));
+    assert!(!ascii::fits(&ascii_dict, 9));
+
+    let (general_tokens, general_words) = tokenize_general(&dict, 2, 3);
+    let mut tokens = Tokens::new();
+    assert_eq!(
+        parser::tokenize_dict(&dict, 2, 3, &mut tokens),
+        general_words
+    );
+    assert_eq!(tokens.entries(), general_tokens.entries());
+}
+
+#[test]
+fn ascii_and_general_prefix_maps_find_the_same_squares() {
+    let words = ["abcdef", "cdghij", "efijkl", "ghabkl", "ijefab", "klcdgh"];
+    let ascii_dict = words
+        .iter()
+        .map(|word| word.to_string())
+        .collect::<WordDict>();
+    // One non-ASCII word of another length sends the dictionary down the general path.
+    let mut general_dict = ascii_dict.clone();
+    general_dict.push("é".to_string());
+
+    let squares = |dict: &WordDict| {
+        let prefix_map = PrefixMap::new(dict, 3, 2, MemoStrategy::for_grid_size(3));
+        let mut squares = ascii_dict
+            .iter()
+            .flat_map(|word| {
+                prefix_map
+                    .symmetric_words_single(prefix_map.tokenize_word(word))
+                    .unwrap()
+            })
+            .map(|solution| prefix_map.stringify_token_matrix((*solution).clone()))
+            .collect::<Vec<_>>();
+        squares.sort_unstable();
+        squares
+    };
+    let ascii_squares = squares(&ascii_dict);
+    assert!(!ascii_squares.is_empty());
+    assert_eq!(ascii_squares, squares(&general_dict));
+}
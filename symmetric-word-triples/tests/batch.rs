@@ -0,0 +1,148 @@
+#![cfg(feature = "native")]
+
+use std::path::PathBuf;
+use symmetric_word_triples::dir_symmetric_words_range_with;
+use symmetric_word_triples::manifest::{BatchOptions, Manifest, ENGINE_VERSION};
+
+fn temp_dirs(name: &str) -> (PathBuf, PathBuf) {
+    let dir = std::env::temp_dir().join(format!(
+        "symmetric_word_triples_{}_{name}",
+        std::process::id()
+    ));
+    std::fs::remove_dir_all(&dir).ok();
+    let (input_dir, output_dir) = (dir.join("input"), dir.join("output"));
+    std::fs::create_dir_all(&input_dir).unwrap();
+    std::fs::create_dir_all(&output_dir).unwrap();
+    (input_dir, output_dir)
+}
+
+#[test]
+fn skips_up_to_date_outputs() {
+    let (input_dir, output_dir) = temp_dirs("batch_incremental");
+    let dictionary = input_dir.join("words.txt");
+    let output = output_dir.join("words/words_grid3_chunk3.txt");
+    std::fs::write(&dictionary, "complaint\nplacement\nintention\n").unwrap();
+
+    let run = |grid_range, batch| {
+        dir_symmetric_words_range_with(&input_dir, &output_dir, grid_range, (3, 3), batch).unwrap()
+    };
+    run((2, 3), BatchOptions::default());
+    let manifest = Manifest::load(&output_dir).unwrap();
+    assert_eq!(manifest.outputs.len(), 2);
+    assert_eq!(
+        manifest.outputs["words/words_grid3_chunk3.txt"].solutions,
+        1
+    );
+    assert_eq!(
+        manifest.outputs["words/words_grid2_chunk3.txt"].solutions,
+        0
+    );
+
+    let stats = std::fs::read_to_string(output_dir.join("words/words_grid3_chunk3_stats.json"));
+    let stats: serde_json::Value = serde_json::from_str(&stats.unwrap()).unwrap();
+    assert_eq!(stats["dictionary_words"], 3);
+    assert_eq!(stats["filtered_words"], 3);
+    assert_eq!(stats["tokens"], 6);
+    assert_eq!(stats["seeds"], 3);
+    assert_eq!(stats["solutions"], 1);
+    assert!(stats["nodes"].as_u64().unwrap() >= 3);
+
+    // An up-to-date output is not written again, a forced run writes it again.
+    std::fs::write(&output, "kept\n").unwrap();
+    run((2, 3), BatchOptions::default());
+    assert_eq!(std::fs::read_to_string(&output).unwrap(), "kept\n");
+    let force = BatchOptions {
+        force: true,
+        ..BatchOptions::default()
+    };
+    run((2, 3), force);
+    let solutions = "complaint placement intention\n";
+    assert_eq!(std::fs::read_to_string(&output).unwrap(), solutions);
+
+    // Outputs of another engine version are recomputed.
+    std::fs::write(&output, "kept\n").unwrap();
+    let mut manifest = Manifest::load(&output_dir).unwrap();
+    for entry in manifest.outputs.values_mut() {
+        entry.engine_version = "0.1.0".to_string();
+    }
+    manifest.save(&output_dir).unwrap();
+    run((2, 3), BatchOptions::default());
+    assert_eq!(std::fs::read_to_string(&output).unwrap(), solutions);
+    let manifest = Manifest::load(&output_dir).unwrap();
+    assert!(manifest
+        .outputs
+        .values()
+        .all(|entry| entry.engine_version == ENGINE_VERSION));
+
+    // A changed dictionary is recomputed.
+    std::fs::write(&output, "kept\n").unwrap();
+    std::fs::write(&dictionary, "complaint\nplacement\nintention\nabc\n").unwrap();
+    run((2, 3), BatchOptions::default());
+    assert_eq!(std::fs::read_to_string(&output).unwrap(), solutions);
+
+    // Clean removes the outputs of combinations that are not part of the run anymore.
+    let clean = BatchOptions {
+        clean: true,
+        ..BatchOptions::default()
+    };
+    run((2, 2), clean);
+    let manifest = Manifest::load(&output_dir).unwrap();
+    assert_eq!(
+        manifest.outputs.keys().collect::<Vec<_>>(),
+        ["words/words_grid2_chunk3.txt"]
+    );
+    assert!(!output.exists());
+
+    std::fs::remove_dir_all(output_dir.parent().unwrap()).ok();
+}
+
+#[test]
+fn indexes_are_only_cached_on_request() {
+    let (input_dir, output_dir) = temp_dirs("batch_index_dir");
+    std::fs::write(
+        input_dir.join("words.txt"),
+        "complaint\nplacement\nintention\n",
+    )
+    .unwrap();
+    let index_files = |dir: &std::path::Path| {
+        walk(dir)
+            .into_iter()
+            .filter(|path| path.extension().is_some_and(|extension| extension == "idx"))
+            .count()
+    };
+
+    dir_symmetric_words_range_with(
+        &input_dir,
+        &output_dir,
+        (3, 3),
+        (3, 3),
+        BatchOptions::default(),
+    )
+    .unwrap();
+    assert_eq!(index_files(&output_dir), 0);
+
+    let index_dir = output_dir.parent().unwrap().join("index");
+    let batch = BatchOptions {
+        force: true,
+        index_dir: Some(index_dir.clone()),
+        ..BatchOptions::default()
+    };
+    dir_symmetric_words_range_with(&input_dir, &output_dir, (3, 3), (3, 3), batch).unwrap();
+    assert_eq!(index_files(&output_dir), 0);
+    assert!(index_dir.join("words_grid3_chunk3.idx").exists());
+
+    std::fs::remove_dir_all(output_dir.parent().unwrap()).ok();
+}
+
+fn walk(dir: &std::path::Path) -> Vec<PathBuf> {
+    let mut paths = vec![];
+    for entry in std::fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            paths.extend(walk(&path));
+        } else {
+            paths.push(path);
+        }
+    }
+    paths
+}
@@ -0,0 +1,79 @@
+use symmetric_word_triples::parser::{
+    crossword::CrosswordMap,
+    matrix::GridShape,
+    memo::MemoStrategy,
+    wordfilter::{PrefixMap, SearchMode, WordDict, WordFilter},
+};
+use symmetric_word_triples::render::RenderStyle;
+
+fn dictionary(words: &[&str]) -> WordDict {
+    words.iter().map(|word| word.to_string()).collect()
+}
+
+#[test]
+fn open_shape_matches_square_search() {
+    let dict = dictionary(&["abcdef", "cdghij", "efijkl", "ghabkl", "ijefab", "klcdgh"]);
+    let prefix_map = PrefixMap::new(&dict, 3, 2, MemoStrategy::for_grid_size(3));
+    let mut squares = dict
+        .iter()
+        .flat_map(|word| {
+            prefix_map
+                .symmetric_words_single(prefix_map.tokenize_word(word))
+                .unwrap()
+        })
+        .map(|solution| prefix_map.stringify_token_matrix((*solution).clone()))
+        .collect::<Vec<_>>();
+    squares.sort_unstable();
+
+    let crossword_map = CrosswordMap::new(&dict, GridShape::open(3), 2).unwrap();
+    let mut grids = crossword_map
+        .solutions(SearchMode::All)
+        .iter()
+        .map(|solution| crossword_map.stringify_rows(solution).join(" "))
+        .collect::<Vec<_>>();
+    grids.sort_unstable();
+
+    assert!(!squares.is_empty());
+    assert_eq!(grids, squares);
+    assert_eq!(crossword_map.count(), squares.len());
+}
+
+#[test]
+fn blocked_cells_split_runs() {
+    let shape = GridShape::parse("..#\n...\n#..\n").unwrap();
+    assert_eq!(shape.run_lengths(), vec![2, 3]);
+
+    let dict = dictionary(&["ab", "bcd", "de", "xy", "abcd"]);
+    let crossword_map = CrosswordMap::new(&dict, shape, 1).unwrap();
+    assert_eq!(crossword_map.word_count(2), 3);
+    assert_eq!(crossword_map.word_count(4), 0);
+
+    let solutions = crossword_map.solutions(SearchMode::All);
+    assert_eq!(solutions.len(), 1);
+    assert_eq!(
+        crossword_map.stringify_rows(&solutions[0]),
+        vec!["ab#", "bcd", "#de"]
+    );
+    assert!(solutions[0].is_symmetric());
+    assert_eq!(
+        RenderStyle::Line.render_blocked(&solutions[0], crossword_map.tokens()),
+        "ab# bcd #de"
+    );
+    assert!(RenderStyle::Html
+        .render_blocked(&solutions[0], crossword_map.tokens())
+        .contains("<td class=\"blocked\"></td>"));
+}
+
+#[test]
+fn shape_must_be_symmetric() {
+    assert!(GridShape::parse("..#\n...\n...\n").is_err());
+    assert!(GridShape::parse("...\n..\n...\n").is_err());
+    assert!(GridShape::parse("..x\n...\nx..\n").is_err());
+}
+
+#[test]
+fn chunk_size_zero_is_rejected() {
+    // Blank lines of a dictionary file are empty words.
+    let dict = dictionary(&["abc", ""]);
+    assert!(CrosswordMap::new(&dict, GridShape::open(3), 0).is_err());
+}
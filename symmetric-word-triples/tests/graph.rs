@@ -0,0 +1,48 @@
+#![cfg(feature = "native")]
+
+use symmetric_word_triples::graph::{CooccurrenceGraph, GraphFormat};
+use symmetric_word_triples::parser::memo::MemoStrategy;
+use symmetric_word_triples::parser::wordfilter::{PrefixMap, SearchMode};
+use symmetric_word_triples::synthetic::SyntheticDictionary;
+
+#[test]
+fn graph_matches_solutions() {
+    let words = SyntheticDictionary {
+        alphabet: vec!['a', 'b', 'c'],
+        word_count: 300,
+        grid_size: 3,
+        chunk_size: 2,
+        chunk_overlap: 0.8,
+        seed: 5,
+    }
+    .generate();
+    let prefix_map = PrefixMap::new(&words, 3, 2, MemoStrategy::Off);
+
+    let mut expected = CooccurrenceGraph::default();
+    let solutions = prefix_map.symmetric_words_all(SearchMode::All);
+    assert!(!solutions.is_empty());
+    for solution in &solutions {
+        expected.add_solution(&prefix_map.stringify_token_rows(solution));
+    }
+    assert_eq!(prefix_map.cooccurrence_graph(), expected);
+}
+
+#[test]
+fn writes_formats() {
+    let mut graph = CooccurrenceGraph::default();
+    graph.add_solution(&["a,b", "c\"d", "a,b"]);
+    graph.add_solution(&["a,b", "e<f"]);
+    assert_eq!(graph.nodes["a,b"], 2);
+    assert_eq!(graph.edges.len(), 2);
+
+    assert_eq!(
+        graph.write(GraphFormat::Csv),
+        "source,target,weight\n\"a,b\",\"c\"\"d\",1\n\"a,b\",e<f,1\n"
+    );
+    let dot = graph.write(GraphFormat::Dot);
+    assert!(dot.contains("  \"a,b\" -- \"c\\\"d\" [weight=1];\n"));
+    let graphml = graph.write(GraphFormat::GraphMl);
+    assert!(graphml.contains("<edge source=\"a,b\" target=\"e&lt;f\">"));
+    assert_eq!("graphml".parse(), Ok(GraphFormat::GraphMl));
+    assert_eq!(GraphFormat::GraphMl.extension(), "graphml");
+}
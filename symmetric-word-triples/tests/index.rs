@@ -0,0 +1,223 @@
+use std::io::Cursor;
+#[cfg(feature = "native")]
+use symmetric_word_triples::parser::wordfilter::SearchMode;
+use symmetric_word_triples::parser::{
+    self,
+    index::{self, IndexHeader, INDEX_VERSION},
+    memo::{MemoStrategy, DEFAULT_MEMO_CAPACITY},
+    wordfilter::{PrefixMap, WordDict, WordFilter},
+};
+
+fn dictionary() -> WordDict {
+    let mut dict = ["complaint", "placement", "intention", "compliant", "apple"]
+        .iter()
+        .map(|word| word.to_string())
+        .collect();
+    parser::len_filter(&mut dict, 9);
+    dict
+}
+
+fn written_index(checksum: u64) -> Vec<u8> {
+    let prefix_map = PrefixMap::new(&dictionary(), 3, 3, MemoStrategy::Off);
+    let mut bytes = vec![];
+    prefix_map.write_index(&mut bytes, checksum).unwrap();
+    bytes
+}
+
+#[test]
+fn round_trip() {
+    let checksum = index::dictionary_checksum(&dictionary());
+    let bytes = written_index(checksum);
+
+    let expected = IndexHeader::new(3, 3, checksum);
+    let header = index::read_index_header(&mut Cursor::new(&bytes)).unwrap();
+    assert_eq!(header, expected);
+
+    let prefix_map = PrefixMap::read_index(
+        Cursor::new(&bytes),
+        &expected,
+        MemoStrategy::Bounded {
+            capacity: DEFAULT_MEMO_CAPACITY,
+        },
+    )
+    .unwrap();
+    let original = PrefixMap::new(&dictionary(), 3, 3, MemoStrategy::Off);
+    assert_eq!(prefix_map.tokens().entries(), original.tokens().entries());
+    assert_eq!(
+        prefix_map.get_prefix_words(&Default::default()),
+        original.get_prefix_words(&Default::default())
+    );
+    let word = prefix_map.tokenize_word("complaint");
+    let solutions = prefix_map.symmetric_words_single(word).unwrap();
+    assert_eq!(
+        prefix_map.stringify_token_matrix((*solutions[0]).clone()),
+        "complaint placement intention"
+    );
+}
+
+#[test]
+fn rejects_mismatches() {
+    let checksum = index::dictionary_checksum(&dictionary());
+    let bytes = written_index(checksum);
+
+    let stale = IndexHeader::new(3, 3, checksum ^ 1);
+    let other_size = IndexHeader::new(3, 2, checksum);
+    let other_version = IndexHeader {
+        version: INDEX_VERSION + 1,
+        ..IndexHeader::new(3, 3, checksum)
+    };
+    for expected in [stale, other_size, other_version] {
+        let error = PrefixMap::read_index(Cursor::new(&bytes), &expected, MemoStrategy::Off)
+            .err()
+            .unwrap();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    assert!(index::read_index_header(&mut Cursor::new(b"not an index")).is_err());
+    let truncated = &bytes[..bytes.len() - 1];
+    let expected = IndexHeader::new(3, 3, checksum);
+    assert!(PrefixMap::read_index(Cursor::new(truncated), &expected, MemoStrategy::Off).is_err());
+}
+
+#[test]
+fn rejects_corrupt_token_lengths() {
+    let checksum = index::dictionary_checksum(&dictionary());
+    let expected = IndexHeader::new(3, 3, checksum);
+    // The header is 32 bytes and the token count 4, then the length of the first token.
+    for (len, kind) in [
+        (u32::MAX, std::io::ErrorKind::InvalidData),
+        (4000, std::io::ErrorKind::UnexpectedEof),
+    ] {
+        let mut bytes = written_index(checksum);
+        bytes[36..40].copy_from_slice(&len.to_le_bytes());
+        let error = PrefixMap::read_index(Cursor::new(&bytes), &expected, MemoStrategy::Off)
+            .err()
+            .unwrap();
+        assert_eq!(error.kind(), kind);
+    }
+}
+
+#[cfg(feature = "native")]
+#[test]
+fn load_or_build_reuses_fresh_index() {
+    use symmetric_word_triples::{index_path, load_or_build_prefix_map};
+
+    let dir = std::env::temp_dir().join(format!(
+        "symmetric_word_triples_index_{}",
+        std::process::id()
+    ));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("words.txt");
+    std::fs::write(&path, "complaint\nplacement\nintention\n").unwrap();
+    let index_file = index_path(&dir, &path, 3, 3);
+
+    let prefix_map = load_or_build_prefix_map(&path, 3, 3, &dir).unwrap();
+    assert_eq!(prefix_map.get_prefix_words(&Default::default()).len(), 3);
+    let written = std::fs::metadata(&index_file).unwrap().modified().unwrap();
+
+    let prefix_map = load_or_build_prefix_map(&path, 3, 3, &dir).unwrap();
+    assert_eq!(prefix_map.get_prefix_words(&Default::default()).len(), 3);
+    assert_eq!(
+        std::fs::metadata(&index_file).unwrap().modified().unwrap(),
+        written
+    );
+
+    // A changed dictionary makes the index stale.
+    // Without "intention" neither word can be in a square, so both are pruned.
+    std::fs::write(&path, "complaint\nplacement\n").unwrap();
+    let prefix_map = load_or_build_prefix_map(&path, 3, 3, &dir).unwrap();
+    assert_eq!(prefix_map.get_prefix_words(&Default::default()).len(), 0);
+    assert_eq!(prefix_map.pruned_words(), 2);
+
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[cfg(feature = "native")]
+#[test]
+fn mapped_index_matches_trie() {
+    // No word starts with "e" or ends with "d", so "de" is pruned.
+    let words = ["aa", "ab", "ac", "ba", "bb", "ca", "cc", "de", "abc"];
+    let mut dict: WordDict = words.iter().map(|word| word.to_string()).collect();
+    parser::len_filter(&mut dict, 2);
+    let prefix_map = PrefixMap::new(&dict, 2, 1, MemoStrategy::Off);
+    assert_eq!(prefix_map.pruned_words(), 1);
+
+    let path = std::env::temp_dir().join(format!(
+        "symmetric_word_triples_mapped_{}.idx",
+        std::process::id()
+    ));
+    let file = std::fs::File::create(&path).unwrap();
+    prefix_map.write_index(file, 7).unwrap();
+    let (header, mapped) = PrefixMap::map_index(&path, MemoStrategy::Off).unwrap();
+    assert_eq!(header, IndexHeader::new(2, 1, 7));
+    assert_eq!(mapped.pruned_words(), 1);
+
+    for prefix in ["", "a", "b", "c", "ab", "cb"] {
+        let prefix = prefix_map.tokenize_word(prefix);
+        assert_eq!(
+            mapped.get_prefix_words(&prefix),
+            prefix_map.get_prefix_words(&prefix)
+        );
+        assert_eq!(mapped.contains(&prefix), prefix_map.contains(&prefix));
+    }
+    for word in &dict {
+        let word = prefix_map.tokenize_word(word);
+        assert_eq!(
+            mapped.symmetric_words_single(word.clone()).unwrap(),
+            prefix_map.symmetric_words_single(word.clone()).unwrap()
+        );
+        assert_eq!(
+            mapped.near_symmetric_words_single(word.clone(), 1, SearchMode::All),
+            prefix_map.near_symmetric_words_single(word, 1, SearchMode::All)
+        );
+    }
+
+    std::fs::write(&path, b"SWTI").unwrap();
+    assert!(PrefixMap::map_index(&path, MemoStrategy::Off).is_err());
+    std::fs::remove_file(&path).ok();
+}
+
+#[cfg(feature = "native")]
+#[test]
+fn rejects_corrupt_mapped_indexes() {
+    // "de" is pruned, so the index ends with a pruned section of one word (12 bytes),
+    // and the last mapped word is the 4 bytes before it.
+    let words = ["aa", "ab", "ac", "ba", "bb", "ca", "cc", "de"];
+    let dict: WordDict = words.iter().map(|word| word.to_string()).collect();
+    let prefix_map = PrefixMap::new(&dict, 2, 1, MemoStrategy::Off);
+    let mut bytes = vec![];
+    prefix_map.write_index(&mut bytes, 7).unwrap();
+    let last_word = bytes.len() - 16;
+
+    let path = std::env::temp_dir().join(format!(
+        "symmetric_word_triples_corrupt_{}.idx",
+        std::process::id()
+    ));
+    let map = |bytes: &[u8]| {
+        std::fs::write(&path, bytes).unwrap();
+        PrefixMap::map_index(&path, MemoStrategy::Off).map(|_| ())
+    };
+    assert!(map(&bytes).is_ok());
+    let patched = |start: usize, patch: &[u8]| {
+        let mut bytes = bytes.clone();
+        bytes[start..start + patch.len()].copy_from_slice(patch);
+        bytes
+    };
+
+    // Grid sizes that overflow the size of the words, and zero sizes.
+    for grid_size in [u64::MAX, u64::MAX / 2 + 1, 0] {
+        let error = map(&patched(8, &grid_size.to_le_bytes())).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    }
+    let error = map(&patched(16, &0u64.to_le_bytes())).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+
+    let error = map(&patched(last_word, &500u16.to_le_bytes())).unwrap_err();
+    assert_eq!(error.to_string(), "Unknown token 500.");
+    let error = map(&patched(last_word, &[0, 0, 0, 0])).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "The words of the index are not in ascending order."
+    );
+    std::fs::remove_file(&path).ok();
+}
@@ -0,0 +1,98 @@
+#![cfg(feature = "native")]
+
+use symmetric_word_triples::job::{JobFile, OutputFormat, SizeRange};
+
+#[test]
+fn runs_every_combination() {
+    let dir =
+        std::env::temp_dir().join(format!("symmetric_word_triples_{}_job", std::process::id()));
+    std::fs::remove_dir_all(&dir).ok();
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join("words.txt"),
+        "complaint\nplacement\nintention\nab\nba\nbb\n",
+    )
+    .unwrap();
+    let job_path = dir.join("jobs.toml");
+    std::fs::write(
+        &job_path,
+        r#"
+[[jobs]]
+dictionary = "words.txt"
+grid = [2, 3]
+chunk = 3
+output = "out"
+index_dir = "index"
+
+[[jobs]]
+dictionary = "words.txt"
+grid = 2
+chunk = 1
+words = ["ab"]
+format = "json"
+output = "out"
+
+[[jobs]]
+dictionary = "words.txt"
+grid = 2
+chunk = 1
+words = ["ab", "unknown", "abc"]
+output = "out"
+
+[[jobs]]
+dictionary = "missing.txt"
+grid = 3
+chunk = 3
+format = "count"
+output = "out"
+"#,
+    )
+    .unwrap();
+
+    let job_file = JobFile::load(&job_path).unwrap();
+    job_file.validate().unwrap();
+    assert_eq!(job_file.jobs[0].grid, SizeRange::Range(2, 3));
+    assert_eq!(job_file.jobs[3].format, OutputFormat::Count);
+
+    let reports = job_file.run(&dir);
+    assert_eq!(reports.len(), 5);
+    assert_eq!(reports[0].solutions, 0);
+    assert!(reports[0].output.is_none());
+    assert_eq!(reports[1].solutions, 1);
+    let lines = std::fs::read_to_string(dir.join("out/words_grid3_chunk3.txt")).unwrap();
+    assert_eq!(lines, "complaint placement intention\n");
+    assert_eq!(reports[2].solutions, 2);
+    let json = std::fs::read_to_string(dir.join("out/words_grid2_chunk1.json")).unwrap();
+    assert_eq!(json, r#"[["ab","ba"],["ab","bb"]]"#);
+    assert_eq!(
+        reports[3].error.as_deref(),
+        Some("Not words of the dictionary: \"unknown\", \"abc\".")
+    );
+    assert!(reports[3].output.is_none());
+    assert!(reports[4].error.is_some());
+    // Only the job with an index directory caches its indexes.
+    assert!(dir.join("index/words_grid3_chunk3.idx").exists());
+    assert!(!dir.join("index/words_grid2_chunk1.idx").exists());
+    assert!(std::fs::read_dir(dir.join("out"))
+        .unwrap()
+        .all(|entry| entry.unwrap().path().extension().unwrap() != "idx"));
+
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn rejects_invalid_jobs() {
+    let json =
+        r#"{"jobs": [{"dictionary": "a.txt", "grid": [4, 2], "chunk": 1, "output": "out"}]}"#;
+    let path = std::env::temp_dir().join(format!(
+        "symmetric_word_triples_{}_jobs.json",
+        std::process::id()
+    ));
+    std::fs::write(&path, json).unwrap();
+    let job_file = JobFile::load(&path).unwrap();
+    assert!(job_file.validate().is_err());
+
+    std::fs::write(&path, r#"{"jobs": [{"dictionary": "a.txt", "grids": 3}]}"#).unwrap();
+    assert!(JobFile::load(&path).is_err());
+    std::fs::remove_file(&path).ok();
+}
@@ -0,0 +1,310 @@
+use proptest::prelude::*;
+use std::collections::BTreeSet;
+use std::io::Cursor;
+use std::sync::Arc;
+use symmetric_word_triples::parser::{
+    self,
+    index::{self, IndexHeader},
+    matrix::TokenMatrix,
+    memo::{MemoStrategy, DEFAULT_MEMO_CAPACITY},
+    wordfilter::{PrefixMap, SearchMode, WordDict, WordFilter},
+};
+use symmetric_word_triples::synthetic::SyntheticDictionary;
+
+type Square = Vec<String>;
+
+/// Naive reference solver: tries every sequence of `grid_size` words and keeps the ones
+/// whose chunk at row i, column j equals the chunk at row j, column i.
+fn oracle(dict: &WordDict, grid_size: usize, chunk_size: usize) -> BTreeSet<Square> {
+    near_oracle(dict, grid_size, chunk_size, 0)
+        .into_iter()
+        .map(|(square, _)| square)
+        .collect()
+}
+
+/// Same as `oracle`, but keeps the sequences with at most `max_mismatches` cells above the
+/// diagonal that differ from their mirror cell, together with these cells.
+fn near_oracle(
+    dict: &WordDict,
+    grid_size: usize,
+    chunk_size: usize,
+    max_mismatches: usize,
+) -> BTreeSet<(Square, Vec<(usize, usize)>)> {
+    let words = dict
+        .iter()
+        .filter(|word| word.len() == grid_size * chunk_size)
+        .cloned()
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .map(|word| parser::chunkify(&word, chunk_size))
+        .filter(|chunks| chunks.len() == grid_size)
+        .collect::<Vec<_>>();
+
+    let mut squares = BTreeSet::new();
+    let mut rows = vec![0; grid_size];
+    if words.is_empty() {
+        return squares;
+    }
+    loop {
+        let mismatches = (0..grid_size)
+            .flat_map(|i| (i + 1..grid_size).map(move |j| (i, j)))
+            .filter(|&(i, j)| words[rows[i]][j] != words[rows[j]][i])
+            .collect::<Vec<_>>();
+        if mismatches.len() <= max_mismatches {
+            let square = rows.iter().map(|&row| words[row].concat()).collect();
+            squares.insert((square, mismatches));
+        }
+        // Next combination, like counting in base `words.len()`.
+        let Some(position) = (0..grid_size).rev().find(|&i| rows[i] + 1 < words.len()) else {
+            return squares;
+        };
+        rows[position] += 1;
+        rows[position + 1..].fill(0);
+    }
+}
+
+fn squares<'a>(
+    prefix_map: &PrefixMap,
+    solutions: impl IntoIterator<Item = &'a TokenMatrix>,
+) -> BTreeSet<Square> {
+    solutions
+        .into_iter()
+        .map(|solution| prefix_map.stringify_token_rows(solution))
+        .collect()
+}
+
+/// Solutions of every word of the dictionary in the first row.
+fn engine(prefix_map: &PrefixMap) -> BTreeSet<Square> {
+    let seeds = prefix_map.get_prefix_words(&Default::default());
+    let solution_sets = seeds
+        .iter()
+        .map(|seed| prefix_map.symmetric_words_single((**seed).clone()).unwrap())
+        .collect::<Vec<_>>();
+    squares(prefix_map, solution_sets.iter().flatten().map(|s| &**s))
+}
+
+/// A dictionary of words over a small alphabet, so that there are many squares,
+/// together with some words of other lengths that have to be ignored.
+fn dictionary_strategy() -> impl Strategy<Value = (WordDict, usize, usize)> {
+    (1usize..=3, 1usize..=2).prop_flat_map(|(grid_size, chunk_size)| {
+        let word_len = grid_size * chunk_size;
+        let word = prop::collection::vec(prop::sample::select(vec!['a', 'b']), word_len)
+            .prop_map(|chars| chars.into_iter().collect::<String>());
+        let words = prop::collection::vec(word, 0..10);
+        let others = prop::collection::vec("[abc]{1,7}".prop_map(String::from), 0..4);
+        (words, others).prop_map(move |(mut words, others)| {
+            words.extend(others);
+            (words, grid_size, chunk_size)
+        })
+    })
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(128))]
+
+    #[test]
+    fn engines_match_oracle((dict, grid_size, chunk_size) in dictionary_strategy()) {
+        let expected = oracle(&dict, grid_size, chunk_size);
+
+        let mut filtered = dict.clone();
+        parser::len_filter(&mut filtered, grid_size * chunk_size);
+        // A memo table of two prefixes evicts all the time.
+        let memos = [MemoStrategy::Off, MemoStrategy::Bounded { capacity: 2 }, MemoStrategy::Bounded { capacity: DEFAULT_MEMO_CAPACITY }];
+        for memo in memos {
+            let prefix_map = PrefixMap::new(&filtered, grid_size, chunk_size, memo);
+            prop_assert_eq!(&engine(&prefix_map), &expected);
+
+            let counted = prefix_map
+                .get_prefix_words(&Default::default())
+                .iter()
+                .map(|seed| prefix_map.count_symmetric_words_single((**seed).clone()).unwrap())
+                .sum::<usize>();
+            prop_assert_eq!(counted, expected.len());
+
+            let prefix_map = Arc::new(prefix_map);
+            let iterated = prefix_map
+                .get_prefix_words(&Default::default())
+                .iter()
+                .flat_map(|seed| prefix_map.symmetric_words_iter((**seed).clone()).unwrap())
+                .collect::<Vec<_>>();
+            prop_assert_eq!(&squares(&prefix_map, &iterated), &expected);
+        }
+
+        let prefix_map = PrefixMap::new(&filtered, grid_size, chunk_size, MemoStrategy::Off);
+        // Pruning keeps every word of a solution.
+        let kept = prefix_map
+            .get_prefix_words(&Default::default())
+            .iter()
+            .map(|word| prefix_map.stringify_token_word(word))
+            .collect::<BTreeSet<_>>();
+        prop_assert!(expected.iter().flatten().all(|row| kept.contains(row)));
+        let checksum = index::dictionary_checksum(&dict);
+        let mut bytes = vec![];
+        prefix_map.write_index(&mut bytes, checksum).unwrap();
+        let header = IndexHeader::new(grid_size, chunk_size, checksum);
+        let loaded = PrefixMap::read_index(Cursor::new(&bytes), &header, MemoStrategy::Bounded { capacity: DEFAULT_MEMO_CAPACITY }).unwrap();
+        prop_assert_eq!(&engine(&loaded), &expected);
+        prop_assert_eq!(loaded.pruned_words(), prefix_map.pruned_words());
+
+        #[cfg(feature = "native")]
+        {
+            let all = prefix_map.symmetric_words_all(SearchMode::All);
+            prop_assert_eq!(&squares(&prefix_map, all.iter().map(|s| &**s)), &expected);
+
+            let path = std::env::temp_dir().join(format!(
+                "symmetric_word_triples_oracle_{}_{:x}.idx",
+                std::process::id(),
+                checksum ^ (grid_size * 16 + chunk_size) as u64
+            ));
+            std::fs::write(&path, &bytes).unwrap();
+            let (_, mapped) = PrefixMap::map_index(&path, MemoStrategy::Off).unwrap();
+            std::fs::remove_file(&path).ok();
+            prop_assert_eq!(&engine(&mapped), &expected);
+        }
+    }
+
+    #[test]
+    fn containing_matches_oracle((dict, grid_size, chunk_size) in dictionary_strategy()) {
+        let expected = oracle(&dict, grid_size, chunk_size);
+        let mut filtered = dict.clone();
+        parser::len_filter(&mut filtered, grid_size * chunk_size);
+        let prefix_map = PrefixMap::new(&filtered, grid_size, chunk_size, MemoStrategy::Bounded { capacity: DEFAULT_MEMO_CAPACITY });
+
+        for word in prefix_map.get_prefix_words(&Default::default()) {
+            let text = prefix_map.stringify_token_word(&word);
+            let containing = expected
+                .iter()
+                .filter(|square| square.contains(&text))
+                .cloned()
+                .collect::<BTreeSet<_>>();
+            let solutions = prefix_map
+                .symmetric_words_containing((*word).clone(), SearchMode::All)
+                .unwrap();
+            prop_assert_eq!(solutions.len(), containing.len());
+            prop_assert_eq!(&squares(&prefix_map, solutions.iter().map(|s| &**s)), &containing);
+            let count = prefix_map.count_symmetric_words_containing((*word).clone()).unwrap();
+            prop_assert_eq!(count, containing.len());
+        }
+
+        let words = prefix_map.get_prefix_words(&Default::default());
+        for pair in words.iter().flat_map(|a| words.iter().map(move |b| [a, b])) {
+            let texts = pair.map(|word| prefix_map.stringify_token_word(word));
+            let containing = expected
+                .iter()
+                .filter(|square| texts.iter().all(|text| square.contains(text)))
+                .cloned()
+                .collect::<BTreeSet<_>>();
+            let required = pair.map(|word| (**word).clone());
+            let solutions = prefix_map
+                .symmetric_words_containing_all(&required, SearchMode::All)
+                .unwrap();
+            prop_assert_eq!(solutions.len(), containing.len());
+            prop_assert_eq!(&squares(&prefix_map, solutions.iter().map(|s| &**s)), &containing);
+            let count = prefix_map.count_symmetric_words_containing_all(&required).unwrap();
+            prop_assert_eq!(count, containing.len());
+        }
+    }
+
+    #[test]
+    fn near_misses_match_oracle(
+        (dict, grid_size, chunk_size) in dictionary_strategy(),
+        max_mismatches in 0usize..3,
+    ) {
+        let expected = near_oracle(&dict, grid_size, chunk_size, max_mismatches);
+        let mut filtered = dict.clone();
+        parser::len_filter(&mut filtered, grid_size * chunk_size);
+        let prefix_map = PrefixMap::new(&filtered, grid_size, chunk_size, MemoStrategy::Bounded { capacity: DEFAULT_MEMO_CAPACITY });
+
+        // Every word of the dictionary, since the prefix map prunes the words that can't be
+        // in a symmetric square.
+        let mut found = BTreeSet::new();
+        for word in filtered.iter().collect::<BTreeSet<_>>() {
+            let near_misses = prefix_map
+                .near_symmetric_words_single(prefix_map.tokenize_word(word), max_mismatches, SearchMode::All)
+                .unwrap();
+            for near_miss in near_misses {
+                let mut mismatches = near_miss.mismatches;
+                mismatches.sort_unstable();
+                let square = prefix_map.stringify_token_rows(&near_miss.square);
+                prop_assert!(found.insert((square, mismatches)));
+            }
+        }
+        prop_assert_eq!(found, expected);
+    }
+
+    #[test]
+    fn limited_modes_return_valid_subsets(
+        (dict, grid_size, chunk_size) in dictionary_strategy(),
+        limit in 1usize..4,
+        seed in any::<u64>(),
+    ) {
+        let expected = oracle(&dict, grid_size, chunk_size);
+        let mut filtered = dict.clone();
+        parser::len_filter(&mut filtered, grid_size * chunk_size);
+        let prefix_map = PrefixMap::new(&filtered, grid_size, chunk_size, MemoStrategy::Bounded { capacity: DEFAULT_MEMO_CAPACITY });
+
+        for word in prefix_map.get_prefix_words(&Default::default()) {
+            let all = prefix_map.symmetric_words_single((*word).clone()).unwrap();
+            for mode in [SearchMode::First { limit }, SearchMode::Sample { limit, seed, stream: 0 }] {
+                let solutions = prefix_map.symmetric_words_single_with((*word).clone(), mode).unwrap();
+                prop_assert_eq!(solutions.len(), all.len().min(limit));
+                let found = squares(&prefix_map, solutions.iter().map(|s| &**s));
+                prop_assert_eq!(found.len(), solutions.len());
+                prop_assert!(found.is_subset(&expected));
+            }
+        }
+    }
+}
+
+#[test]
+fn oracle_finds_known_square() {
+    let dict = ["complaint", "placement", "intention", "apple"]
+        .iter()
+        .map(|word| word.to_string())
+        .collect();
+    let expected = vec!["complaint", "placement", "intention"]
+        .into_iter()
+        .map(String::from)
+        .collect::<Square>();
+    assert_eq!(oracle(&dict, 3, 3), BTreeSet::from([expected]));
+}
+
+#[test]
+fn synthetic_dictionaries_are_reproducible() {
+    let synthetic = SyntheticDictionary {
+        alphabet: vec!['a', 'b', 'c'],
+        word_count: 200,
+        grid_size: 3,
+        chunk_size: 2,
+        chunk_overlap: 0.8,
+        seed: 7,
+    };
+    let dict = synthetic.generate();
+    assert_eq!(dict, synthetic.generate());
+    assert_eq!(dict.len(), 200);
+    assert!(dict.iter().all(|word| word.len() == 6));
+    assert_ne!(
+        dict,
+        SyntheticDictionary {
+            seed: 8,
+            ..synthetic
+        }
+        .generate()
+    );
+
+    let expected = oracle(&dict, 3, 2);
+    assert!(!expected.is_empty());
+    let mut filtered = dict.clone();
+    parser::len_filter(&mut filtered, 6);
+    assert_eq!(
+        engine(&PrefixMap::new(
+            &filtered,
+            3,
+            2,
+            MemoStrategy::Bounded {
+                capacity: DEFAULT_MEMO_CAPACITY
+            }
+        )),
+        expected
+    );
+}
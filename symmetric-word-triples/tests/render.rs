@@ -0,0 +1,90 @@
+use symmetric_word_triples::parser::memo::MemoStrategy;
+use symmetric_word_triples::parser::wordfilter::{NearMiss, PrefixMap, WordFilter};
+use symmetric_word_triples::render::RenderStyle;
+
+fn solution() -> (
+    PrefixMap,
+    symmetric_word_triples::parser::matrix::TokenMatrix,
+) {
+    let words = ["complaint", "placement", "intention"]
+        .map(String::from)
+        .to_vec();
+    let prefix_map = PrefixMap::new(&words, 3, 3, MemoStrategy::Off);
+    let word = prefix_map.tokenize_word("complaint");
+    let solution = (*prefix_map.symmetric_words_single(word).unwrap()[0]).clone();
+    (prefix_map, solution)
+}
+
+#[test]
+fn renders_grids() {
+    let (prefix_map, solution) = solution();
+    let tokens = prefix_map.tokens();
+
+    assert_eq!(
+        RenderStyle::Line.render(&solution, tokens),
+        "complaint placement intention"
+    );
+    assert_eq!(
+        RenderStyle::Text.render(&solution, tokens),
+        "com pla int\npla cem ent\nint ent ion\n"
+    );
+
+    let html = RenderStyle::Html.render(&solution, tokens);
+    assert!(html.starts_with("<table class=\"square\">\n"));
+    assert!(html.contains("<tr><td>pla</td><td class=\"diagonal\">cem</td><td>ent</td></tr>"));
+
+    let svg = RenderStyle::Svg.render(&solution, tokens);
+    assert!(
+        svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"138\" height=\"96\"")
+    );
+    assert_eq!(svg.matches("<rect ").count(), 9);
+    assert_eq!(svg.matches("fill=\"#fde68a\"").count(), 3);
+    assert!(svg.contains(">ion</text>"));
+}
+
+#[test]
+fn renders_documents() {
+    let (prefix_map, solution) = solution();
+    let tokens = prefix_map.tokens();
+    let solutions = [solution.clone(), solution];
+
+    assert_eq!(
+        RenderStyle::Line.render_document(&solutions, tokens),
+        "complaint placement intention\ncomplaint placement intention\n"
+    );
+    let text = RenderStyle::Text.render_document(&solutions, tokens);
+    assert_eq!(text.split("\n\n").count(), 2);
+    let html = RenderStyle::Html.render_document(&solutions, tokens);
+    assert_eq!(html.matches("<table").count(), 2);
+    assert!(html.ends_with("</body>\n</html>\n"));
+    let svg = RenderStyle::Svg.render_document(&solutions, tokens);
+    assert!(svg.contains("height=\"208\""));
+    assert_eq!(svg.matches("<svg").count(), 1);
+
+    assert_eq!("svg".parse(), Ok(RenderStyle::Svg));
+    assert!("png".parse::<RenderStyle>().is_err());
+}
+
+#[test]
+fn marks_mismatched_cells() {
+    let (prefix_map, square) = solution();
+    let tokens = prefix_map.tokens();
+    let near_misses = [NearMiss {
+        square,
+        mismatches: vec![(0, 2)],
+    }];
+
+    assert_eq!(
+        RenderStyle::Line.render_near_miss_document(&near_misses, tokens),
+        "complaint placement intention | (1,3)\n"
+    );
+    assert_eq!(
+        RenderStyle::Text.render_near_miss_document(&near_misses, tokens),
+        "com  pla int*\npla  cem ent\nint* ent ion\n"
+    );
+    let html = RenderStyle::Html.render_near_miss_document(&near_misses, tokens);
+    assert_eq!(html.matches("<td class=\"mismatch\">int</td>").count(), 2);
+    let svg = RenderStyle::Svg.render_near_miss_document(&near_misses, tokens);
+    assert_eq!(svg.matches("fill=\"#fca5a5\"").count(), 2);
+    assert_eq!(svg.matches("fill=\"#fde68a\"").count(), 3);
+}
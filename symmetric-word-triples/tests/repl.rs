@@ -0,0 +1,62 @@
+use symmetric_word_triples::repl::Repl;
+
+fn repl() -> Repl {
+    let words = ["complaint", "placement", "intention", "ab", "ba", "bb"];
+    Repl::new(words.iter().map(|w| w.to_string()).collect(), 3, 3)
+}
+
+#[test]
+fn seed_and_resize() {
+    let mut repl = repl();
+    let output = repl.execute("seed complaint").unwrap();
+    assert!(output.starts_with("complaint placement intention\n"));
+    assert!(repl
+        .execute("seed intention")
+        .unwrap()
+        .starts_with("No results."));
+    let output = repl.execute("any intention").unwrap();
+    assert!(output.starts_with("complaint placement intention\n"));
+    let output = repl.execute("any intention placement").unwrap();
+    assert!(output.starts_with("complaint placement intention\n"));
+    assert!(repl
+        .execute("any intention complaint placement intention")
+        .is_ok());
+
+    repl.execute("render text").unwrap();
+    let output = repl.execute("seed complaint").unwrap();
+    assert!(output.starts_with("com pla int\npla cem ent\nint ent ion\n-- page 1/1"));
+    assert!(repl.execute("render png").is_err());
+    repl.execute("render line").unwrap();
+
+    repl.execute("grid 2").unwrap();
+    repl.execute("chunk 1").unwrap();
+    let output = repl.execute("seed ab").unwrap();
+    assert!(output.starts_with("ab ba\nab bb\n"));
+
+    assert!(repl.execute("seed complaint").is_err());
+}
+
+#[test]
+fn paging() {
+    let mut repl = repl();
+    repl.execute("page 2").unwrap();
+    let output = repl.execute("tokens").unwrap();
+    assert!(output.ends_with("-- page 1/3, 6 results --"));
+
+    let output = repl.execute("next").unwrap();
+    assert!(output.starts_with("     2 int\n     3 ent"));
+    repl.execute("next").unwrap();
+    let output = repl.execute("next").unwrap();
+    assert!(output.ends_with("-- page 3/3, 6 results --"));
+}
+
+#[test]
+fn run_until_quit() {
+    let mut repl = repl();
+    let mut output = vec![];
+    repl.run("prefix com\nquit\nprefix pla\n".as_bytes(), &mut output)
+        .unwrap();
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.contains("complaint"));
+    assert!(!output.contains("placement"));
+}
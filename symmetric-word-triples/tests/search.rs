@@ -0,0 +1,291 @@
+#[cfg(feature = "native")]
+use std::path::PathBuf;
+use std::sync::Arc;
+#[cfg(feature = "native")]
+use symmetric_word_triples::count_symmetric_words_in_file_mt;
+use symmetric_word_triples::parser::{
+    self,
+    memo::{MemoStrategy, AUTO_PAUSE, AUTO_WINDOW, DEFAULT_MEMO_CAPACITY},
+    token::{Token, TokenWord},
+    wordfilter::{PrefixMap, SearchMode, WordDict, WordFilter},
+};
+use symmetric_word_triples::synthetic::SyntheticDictionary;
+
+fn dictionary(words: &[&str]) -> WordDict {
+    words.iter().map(|word| word.to_string()).collect()
+}
+
+#[cfg(feature = "native")]
+fn temp_dictionary_file(name: &str, words: &[&str]) -> PathBuf {
+    let path = std::env::temp_dir().join(format!(
+        "symmetric_word_triples_{}_{name}.txt",
+        std::process::id()
+    ));
+    std::fs::write(&path, words.join("\n")).unwrap();
+    path
+}
+
+#[test]
+fn count_matches_solution_set() {
+    let mut dict = dictionary(&["ab", "ba", "aa", "bb", "abc"]);
+    parser::len_filter(&mut dict, 2);
+    let memos = [
+        MemoStrategy::Off,
+        MemoStrategy::Bounded {
+            capacity: DEFAULT_MEMO_CAPACITY,
+        },
+    ];
+    for memo in memos {
+        let prefix_map = PrefixMap::new(&dict, 2, 1, memo);
+        for word in &dict {
+            let word = prefix_map.tokenize_word(word);
+            let solutions = prefix_map.symmetric_words_single(word.clone()).unwrap();
+            let count = prefix_map.count_symmetric_words_single(word).unwrap();
+            assert_eq!(solutions.len(), count);
+            assert_eq!(count, 2);
+        }
+    }
+}
+
+#[cfg(feature = "native")]
+#[test]
+fn count_file_per_word() {
+    let path = temp_dictionary_file(
+        "count_file_per_word",
+        &["complaint", "placement", "intention", "apple", "orange"],
+    );
+    let counted = count_symmetric_words_in_file_mt(&path, 3, 3).unwrap();
+    std::fs::remove_file(&path).ok();
+
+    assert_eq!(counted.total, 1);
+    assert_eq!(counted.per_word, vec![("complaint".to_string(), 1)]);
+}
+
+#[test]
+fn first_stops_at_limit() {
+    let dict = dictionary(&["aa", "ab", "ac", "ba", "bb", "bc", "ca", "cb", "cc"]);
+    let prefix_map = PrefixMap::new(&dict, 2, 1, MemoStrategy::Off);
+    let word = prefix_map.tokenize_word("ab");
+
+    let all = prefix_map.symmetric_words_single(word.clone()).unwrap();
+    let first = prefix_map
+        .symmetric_words_single_with(word.clone(), SearchMode::First { limit: 2 })
+        .unwrap();
+    let none = prefix_map
+        .symmetric_words_single_with(word, SearchMode::First { limit: 0 })
+        .unwrap();
+
+    assert_eq!(all.len(), 3);
+    assert_eq!(first[..], all[..2]);
+    assert!(none.is_empty());
+}
+
+#[test]
+fn sample_is_reproducible() {
+    let dict = dictionary(&["aa", "ab", "ac", "ba", "bb", "bc", "ca", "cb", "cc"]);
+    let prefix_map = PrefixMap::new(
+        &dict,
+        2,
+        1,
+        MemoStrategy::Bounded {
+            capacity: DEFAULT_MEMO_CAPACITY,
+        },
+    );
+    let word = prefix_map.tokenize_word("ab");
+    let mode = SearchMode::Sample {
+        limit: 2,
+        seed: 42,
+        stream: 0,
+    };
+
+    let all = prefix_map.symmetric_words_single(word.clone()).unwrap();
+    let sample = prefix_map
+        .symmetric_words_single_with(word.clone(), mode)
+        .unwrap();
+    let again = prefix_map.symmetric_words_single_with(word, mode).unwrap();
+
+    assert_eq!(sample.len(), 2);
+    assert_eq!(sample, again);
+    assert!(sample.iter().all(|solution| all.contains(solution)));
+}
+
+#[test]
+fn iter_matches_solution_set() {
+    let dict = dictionary(&[
+        "aaa", "aab", "aba", "abb", "baa", "bab", "bba", "bbb", "abc", "bca", "cab",
+    ]);
+    let prefix_map = Arc::new(PrefixMap::new(
+        &dict,
+        3,
+        1,
+        MemoStrategy::Bounded {
+            capacity: DEFAULT_MEMO_CAPACITY,
+        },
+    ));
+    for word in &dict {
+        let word = prefix_map.tokenize_word(word);
+        let solutions = prefix_map.symmetric_words_single(word.clone()).unwrap();
+        let iterated = prefix_map
+            .symmetric_words_iter(word)
+            .unwrap()
+            .map(Arc::new)
+            .collect::<Vec<_>>();
+        assert_eq!(solutions, iterated);
+    }
+}
+
+#[test]
+fn stats_count_the_search() {
+    let dict = dictionary(&["aa", "ab", "ba", "bb"]);
+    let prefix_map = PrefixMap::new(
+        &dict,
+        2,
+        1,
+        MemoStrategy::Bounded {
+            capacity: DEFAULT_MEMO_CAPACITY,
+        },
+    );
+    for word in &dict {
+        let word = prefix_map.tokenize_word(word);
+        prefix_map.count_symmetric_words_single(word).unwrap();
+    }
+    let stats = prefix_map.stats();
+    // 4 first rows, each with 2 candidates for the second row.
+    assert_eq!(stats.nodes, 4 + 4 * 2);
+    assert_eq!(stats.branching_per_depth(), vec![0.0, 2.0]);
+    assert_eq!(stats.table_misses, 2);
+    assert_eq!(stats.table_hit_rate(), Some(0.5));
+
+    prefix_map.reset_stats();
+    assert_eq!(prefix_map.stats().nodes, 0);
+}
+
+#[test]
+fn impossible_words_are_pruned() {
+    // No word ends with "c" or starts with "d", so "cd" can't be any row.
+    let dict = dictionary(&["ab", "ba", "cd"]);
+    let prefix_map = PrefixMap::new(&dict, 2, 1, MemoStrategy::Off);
+    assert_eq!(prefix_map.pruned_words(), 1);
+    assert_eq!(prefix_map.get_prefix_words(&Default::default()).len(), 2);
+
+    let cd = prefix_map.tokenize_word("cd");
+    assert!(prefix_map.contains(&cd));
+    assert!(prefix_map
+        .symmetric_words_single(cd.clone())
+        .unwrap()
+        .is_empty());
+    // Near misses don't need symmetric rows, so they still use the pruned words.
+    let near_misses = prefix_map
+        .near_symmetric_words_single(cd, 1, SearchMode::All)
+        .unwrap();
+    assert_eq!(near_misses.len(), 3);
+}
+
+#[test]
+fn memo_table_stays_bounded() {
+    let mut dict = SyntheticDictionary {
+        alphabet: vec!['a', 'b', 'c'],
+        word_count: 300,
+        grid_size: 3,
+        chunk_size: 2,
+        chunk_overlap: 0.8,
+        seed: 1,
+    }
+    .generate();
+    parser::len_filter(&mut dict, 6);
+    let count_all = |prefix_map: &PrefixMap| {
+        prefix_map
+            .get_prefix_words(&Default::default())
+            .iter()
+            .map(|word| {
+                prefix_map
+                    .count_symmetric_words_single((**word).clone())
+                    .unwrap()
+            })
+            .sum::<usize>()
+    };
+
+    let expected = count_all(&PrefixMap::new(&dict, 3, 2, MemoStrategy::Off));
+    assert!(expected > 0);
+    let bounded = PrefixMap::new(&dict, 3, 2, MemoStrategy::Bounded { capacity: 8 });
+    assert_eq!(count_all(&bounded), expected);
+    assert!(bounded.memo_enabled());
+    assert!(bounded.memo_len() <= 8);
+    assert!(bounded.stats().table_hits > 0);
+
+    let off = PrefixMap::new(&dict, 3, 2, MemoStrategy::Off);
+    assert!(!off.memo_enabled());
+    count_all(&off);
+    assert_eq!(off.memo_len(), 0);
+    assert_eq!(off.stats().table_hit_rate(), None);
+
+    assert_eq!(MemoStrategy::for_grid_size(2), MemoStrategy::Off);
+    assert!(matches!(
+        MemoStrategy::for_grid_size(5),
+        MemoStrategy::Auto { .. }
+    ));
+}
+
+#[test]
+fn auto_memo_table_retries_after_a_pause() {
+    let dict = dictionary(&["ab", "ba"]);
+    let prefix_map = PrefixMap::new(&dict, 2, 1, MemoStrategy::Auto { capacity: 16 });
+    let prefix = |i: u64| TokenWord(vec![Token((i >> 8) as u16), Token(i as u16 & 255)]);
+
+    // A window of prefixes that never repeat turns the table off.
+    for i in 0..AUTO_WINDOW {
+        prefix_map.get_prefix_words_table(&prefix(i));
+    }
+    assert!(!prefix_map.memo_enabled());
+    assert_eq!(prefix_map.memo_len(), 0);
+
+    // It comes back after the pause, and stays on while the lookups hit.
+    for i in 0..AUTO_PAUSE - 1 {
+        prefix_map.get_prefix_words_table(&prefix(i));
+        assert!(!prefix_map.memo_enabled());
+    }
+    prefix_map.get_prefix_words_table(&prefix(0));
+    assert!(prefix_map.memo_enabled());
+    for _ in 0..AUTO_WINDOW {
+        prefix_map.get_prefix_words_table(&prefix(0));
+    }
+    assert!(prefix_map.memo_enabled());
+    assert_eq!(prefix_map.memo_len(), 1);
+}
+
+#[test]
+fn required_words_have_to_be_words() {
+    let dict = dictionary(&["ab", "bc", "ca"]);
+    let prefix_map = PrefixMap::new(&dict, 2, 1, MemoStrategy::Off);
+    let ba = prefix_map.tokenize_word("ba");
+    assert!(!prefix_map.contains(&ba));
+    assert!(prefix_map
+        .symmetric_words_containing(ba.clone(), SearchMode::All)
+        .is_err());
+    assert!(prefix_map
+        .count_symmetric_words_containing(ba.clone())
+        .is_err());
+    let ab = prefix_map.tokenize_word("ab");
+    assert!(prefix_map
+        .symmetric_words_containing_all(&[ab, ba], SearchMode::All)
+        .is_err());
+}
+
+#[test]
+fn containing_all_stops_at_the_limit() {
+    let dict = dictionary(&["ab", "ba"]);
+    let prefix_map = PrefixMap::new(&dict, 2, 1, MemoStrategy::Off);
+    let words = [prefix_map.tokenize_word("ab")];
+    let all = prefix_map
+        .symmetric_words_containing_all(&words, SearchMode::All)
+        .unwrap();
+    assert_eq!(all.len(), 2);
+    let first = prefix_map
+        .symmetric_words_containing_all(&words, SearchMode::First { limit: 1 })
+        .unwrap();
+    assert_eq!(first.len(), 1);
+    assert_eq!(
+        prefix_map.count_symmetric_words_containing_all(&words),
+        Ok(2)
+    );
+}
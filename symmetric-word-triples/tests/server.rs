@@ -0,0 +1,191 @@
+#![cfg(feature = "server")]
+
+use serde_json::{json, Value};
+use symmetric_word_triples::server::{QueryServer, ServerConfig};
+
+fn query_server() -> QueryServer {
+    let mut query_server = QueryServer::new(ServerConfig {
+        max_results: 2,
+        ..ServerConfig::default()
+    });
+    let words = [
+        "complaint",
+        "placement",
+        "intention",
+        "aa",
+        "ab",
+        "ba",
+        "bb",
+    ];
+    query_server.insert_dictionary("words", words.iter().map(|w| w.to_string()).collect());
+    query_server
+}
+
+fn post(query_server: &QueryServer, url: &str, body: Value) -> (u16, Value) {
+    let (status, body) = query_server.handle("POST", url, &body.to_string());
+    (status, serde_json::from_str(&body).unwrap())
+}
+
+#[test]
+fn seed_search() {
+    let query_server = query_server();
+    let request = json!({
+        "dictionary": "words",
+        "grid_size": 3,
+        "chunk_size": 3,
+        "word": "complaint",
+    });
+    let (status, body) = post(&query_server, "/seed", request);
+
+    assert_eq!(status, 200);
+    assert_eq!(
+        body["solutions"],
+        json!([["complaint", "placement", "intention"]])
+    );
+    assert_eq!(body["timed_out"], json!(false));
+}
+
+#[test]
+fn results_are_limited() {
+    let query_server = query_server();
+    let request = json!({
+        "dictionary": "words",
+        "grid_size": 2,
+        "chunk_size": 1,
+        "prefix": "",
+        "limit": 10,
+    });
+    let (status, body) = post(&query_server, "/prefix", request);
+
+    assert_eq!(status, 200);
+    assert_eq!(body["total"], json!(4));
+    assert_eq!(body["words"].as_array().unwrap().len(), 2);
+}
+
+#[test]
+fn template_and_count() {
+    let query_server = query_server();
+    let template = json!({
+        "dictionary": "words",
+        "grid_size": 2,
+        "chunk_size": 1,
+        "rows": ["?b", "?a"],
+    });
+    let (_, body) = post(&query_server, "/template", template);
+    assert_eq!(body["solutions"], json!([["ab", "ba"], ["bb", "ba"]]));
+
+    let fixed = json!({
+        "dictionary": "words",
+        "grid_size": 2,
+        "chunk_size": 1,
+        "rows": ["", "ba"],
+    });
+    let (_, body) = post(&query_server, "/template", fixed);
+    assert_eq!(body["solutions"], json!([["ab", "ba"], ["bb", "ba"]]));
+    let unknown = json!({
+        "dictionary": "words",
+        "grid_size": 2,
+        "chunk_size": 1,
+        "rows": ["zz"],
+    });
+    let (status, body) = post(&query_server, "/template", unknown);
+    assert_eq!(status, 200);
+    assert_eq!(body["solutions"], json!([]));
+
+    let count = json!({ "dictionary": "words", "grid_size": 2, "chunk_size": 1 });
+    let (_, body) = post(&query_server, "/count", count);
+    assert_eq!(body["count"], json!(8));
+}
+
+#[test]
+fn template_rows_are_measured_in_bytes() {
+    let mut query_server = QueryServer::new(ServerConfig::default());
+    query_server.insert_dictionary("accents", vec!["éé".to_string(), "ab".to_string()]);
+    for rows in [json!(["éé"]), json!(["é?"]), json!(["", "éé"])] {
+        let template = json!({
+            "dictionary": "accents",
+            "grid_size": 2,
+            "chunk_size": 2,
+            "rows": rows,
+        });
+        let (status, body) = post(&query_server, "/template", template);
+        assert_eq!(status, 200);
+        assert_eq!(body["solutions"], json!([["éé", "éé"]]));
+    }
+    let too_long = json!({
+        "dictionary": "accents",
+        "grid_size": 2,
+        "chunk_size": 2,
+        "rows": ["ééé"],
+    });
+    assert_eq!(post(&query_server, "/template", too_long).0, 400);
+}
+
+#[test]
+fn bad_requests() {
+    let query_server = query_server();
+    let unknown = json!({
+        "dictionary": "missing",
+        "grid_size": 3,
+        "chunk_size": 3,
+        "word": "complaint",
+    });
+    assert_eq!(post(&query_server, "/seed", unknown).0, 400);
+
+    let not_a_word = json!({
+        "dictionary": "words",
+        "grid_size": 3,
+        "chunk_size": 3,
+        "word": "intention",
+    });
+    assert_eq!(post(&query_server, "/seed", not_a_word).0, 200);
+    let not_a_word = json!({
+        "dictionary": "words",
+        "grid_size": 3,
+        "chunk_size": 3,
+        "word": "complains",
+    });
+    assert_eq!(post(&query_server, "/seed", not_a_word).0, 400);
+
+    assert_eq!(query_server.handle("GET", "/nothing", "").0, 404);
+}
+
+#[test]
+fn concurrent_requests_share_one_prefix_map() {
+    let query_server = query_server();
+    let request = json!({
+        "dictionary": "words",
+        "grid_size": 2,
+        "chunk_size": 1,
+        "word": "ab",
+    });
+    let responses = std::thread::scope(|scope| {
+        let handles = (0..8)
+            .map(|_| scope.spawn(|| post(&query_server, "/count", request.clone())))
+            .collect::<Vec<_>>();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect::<Vec<_>>()
+    });
+    for (status, body) in &responses {
+        assert_eq!(*status, 200);
+        assert_eq!(body, &responses[0].1);
+    }
+}
+
+#[test]
+fn sizes_are_bounded() {
+    let query_server = query_server();
+    for (grid_size, chunk_size) in [(usize::MAX, 2), (2, usize::MAX), (9, 1), (2, 17)] {
+        let request = json!({
+            "dictionary": "words",
+            "grid_size": grid_size,
+            "chunk_size": chunk_size,
+            "prefix": "",
+        });
+        let (status, body) = post(&query_server, "/prefix", request);
+        assert_eq!(status, 400);
+        assert!(body["error"].as_str().unwrap().contains("at most"));
+    }
+}
@@ -0,0 +1,154 @@
+#![cfg(feature = "native")]
+
+use std::path::PathBuf;
+use symmetric_word_triples::render::RenderStyle;
+use symmetric_word_triples::shard::{self, Shard, ShardBy};
+use symmetric_word_triples::symmetric_words_in_file_mt;
+use symmetric_word_triples::synthetic::SyntheticDictionary;
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "symmetric_word_triples_{}_{name}",
+        std::process::id()
+    ));
+    std::fs::remove_dir_all(&dir).ok();
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn merged_shards_match_single_run() {
+    let dir = temp_dir("shard_merge");
+    let dictionary = dir.join("words.txt");
+    let words = SyntheticDictionary {
+        alphabet: vec!['a', 'b', 'c'],
+        word_count: 300,
+        grid_size: 3,
+        chunk_size: 2,
+        chunk_overlap: 0.8,
+        seed: 3,
+    }
+    .generate();
+    std::fs::write(&dictionary, words.join("\n")).unwrap();
+
+    let mut expected = symmetric_words_in_file_mt(&dictionary, 3, 2).unwrap();
+    expected.sort_unstable();
+    assert!(!expected.is_empty());
+
+    for by in [ShardBy::Range, ShardBy::Hash] {
+        let shard_dir = dir.join(format!("{by:?}"));
+        for index in 0..3 {
+            let shard = Shard::new(index, 3, by).unwrap();
+            shard::run_shard(&dictionary, &shard_dir, 3, 2, shard, RenderStyle::Line).unwrap();
+        }
+        let output = dir.join(format!("{by:?}.txt"));
+        assert_eq!(
+            shard::merge_shards(&shard_dir, &output, RenderStyle::Line).unwrap(),
+            expected.len()
+        );
+        let merged = std::fs::read_to_string(&output).unwrap();
+        assert_eq!(merged.lines().collect::<Vec<_>>(), expected);
+    }
+
+    // Other JSON files in the directory aren't shard reports.
+    std::fs::write(dir.join("Range/notes.json"), "{}").unwrap();
+    std::fs::write(dir.join("Range/words_shard_list.json"), "[]").unwrap();
+    assert_eq!(
+        shard::merge_shards(
+            &dir.join("Range"),
+            &dir.join("Range.txt"),
+            RenderStyle::Line
+        )
+        .unwrap(),
+        expected.len()
+    );
+
+    let duplicate = dir.join("Hash/copy_grid3_chunk2_shard1of3.json");
+    std::fs::copy(
+        dir.join("Hash/words_grid3_chunk2_shard1of3.json"),
+        &duplicate,
+    )
+    .unwrap();
+    let error = shard::merge_shards(
+        &dir.join("Hash"),
+        &dir.join("duplicate.txt"),
+        RenderStyle::Line,
+    )
+    .unwrap_err();
+    assert_eq!(error.to_string(), "Shard 1 of 3 has more than one report.");
+    assert!(!dir.join("duplicate.txt").exists());
+    std::fs::remove_file(&duplicate).unwrap();
+
+    let renamed = dir.join("Hash/words_grid3_chunk2_shard2of4.json");
+    std::fs::copy(dir.join("Hash/words_grid3_chunk2_shard2of3.json"), &renamed).unwrap();
+    assert!(shard::merge_shards(
+        &dir.join("Hash"),
+        &dir.join("renamed.txt"),
+        RenderStyle::Line
+    )
+    .is_err());
+    std::fs::remove_file(&renamed).unwrap();
+
+    std::fs::remove_file(dir.join("Hash/words_grid3_chunk2_shard1of3.json")).unwrap();
+    let error = shard::merge_shards(
+        &dir.join("Hash"),
+        &dir.join("missing.txt"),
+        RenderStyle::Line,
+    )
+    .unwrap_err();
+    assert_eq!(error.to_string(), "Shard 1 of 3 is missing.");
+    assert!(!dir.join("missing.txt").exists());
+
+    std::fs::write(dir.join("Range/words_grid3_chunk2_shard2of3.txt"), "").unwrap();
+    assert!(shard::merge_shards(
+        &dir.join("Range"),
+        &dir.join("incomplete.txt"),
+        RenderStyle::Line
+    )
+    .is_err());
+}
+
+#[test]
+fn shards_and_merges_are_rendered() {
+    let dir = temp_dir("shard_render");
+    let dictionary = dir.join("words.txt");
+    std::fs::write(&dictionary, "complaint\nplacement\nintention\n").unwrap();
+
+    let shard_dir = dir.join("shards");
+    for index in 0..2 {
+        let shard = Shard::new(index, 2, ShardBy::Range).unwrap();
+        let report =
+            shard::run_shard(&dictionary, &shard_dir, 3, 3, shard, RenderStyle::Html).unwrap();
+        let rendered = std::fs::read_to_string(shard_dir.join(report.rendered.unwrap())).unwrap();
+        assert_eq!(rendered.matches("<table").count(), report.solutions);
+    }
+    assert!(shard_dir.join("words_grid3_chunk3_shard0of2.html").exists());
+
+    let output = dir.join("merged.txt");
+    assert_eq!(
+        shard::merge_shards(&shard_dir, &output, RenderStyle::Text).unwrap(),
+        1
+    );
+    assert_eq!(
+        std::fs::read_to_string(&output).unwrap(),
+        "com pla int\npla cem ent\nint ent ion\n"
+    );
+}
+
+#[test]
+fn parses_shards() {
+    let shard = Shard::parse("2/8", ShardBy::Hash).unwrap();
+    assert_eq!((shard.index, shard.count), (2, 8));
+    assert!(Shard::parse("8/8", ShardBy::Range).is_err());
+    assert!(Shard::parse("2", ShardBy::Range).is_err());
+
+    let seeds = (0..10).map(|i| format!("word{i}")).collect::<Vec<_>>();
+    for by in [ShardBy::Range, ShardBy::Hash] {
+        let mut selected = (0..4)
+            .flat_map(|index| Shard::new(index, 4, by).unwrap().select(&seeds))
+            .cloned()
+            .collect::<Vec<_>>();
+        selected.sort_unstable();
+        assert_eq!(selected, seeds);
+    }
+}
@@ -0,0 +1,71 @@
+use symmetric_word_triples::verify;
+
+fn strings(lines: &[&str]) -> Vec<String> {
+    lines.iter().map(|line| line.to_string()).collect()
+}
+
+#[test]
+fn reports_invalid_and_duplicate_lines() {
+    let dict = strings(&["complaint", "placement", "intention", "apple", "plaintcom"]);
+    let lines = strings(&[
+        "complaint placement intention",
+        "complaint placement",
+        "complaint placement apple",
+        "complaint placement plaintcom",
+        "complaint placement intention",
+    ]);
+    let report = verify::verify_solutions(&lines, &dict, 3, 3);
+    assert_eq!(report.lines, 5);
+    assert_eq!(report.valid, 1);
+    let reasons = report
+        .invalid
+        .iter()
+        .map(|(line_number, _, reason)| (*line_number, reason.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        reasons,
+        [
+            (2, "2 rows instead of 3"),
+            (3, "row 3 \"apple\" doesn't have 3 chunks of size 3"),
+            (4, "column 1 \"complapla\" is not a word"),
+        ]
+    );
+    assert_eq!(
+        report.duplicates,
+        [(5, "complaint placement intention".to_string())]
+    );
+    assert!(!report.is_ok());
+
+    let report = verify::verify_solutions(&lines[..1], &dict, 3, 3);
+    assert!(report.is_ok());
+}
+
+#[cfg(feature = "native")]
+#[test]
+fn rerun_finds_missing_lines() {
+    let dir = std::env::temp_dir().join(format!(
+        "symmetric_word_triples_{}_verify",
+        std::process::id()
+    ));
+    std::fs::create_dir_all(&dir).unwrap();
+    let (dictionary, output) = (dir.join("words.txt"), dir.join("words_grid2_chunk1.txt"));
+    std::fs::write(&dictionary, "ab\nba\naa\nbb\n").unwrap();
+    std::fs::write(&output, "aa aa\nab ba\n").unwrap();
+
+    let report = verify::verify_file(&output, &dictionary, 2, 1, false).unwrap();
+    assert_eq!(report.valid, 2);
+    assert!(report.is_ok());
+
+    let report = verify::verify_file(&output, &dictionary, 2, 1, true).unwrap();
+    assert_eq!(
+        report,
+        verify::VerifyReport {
+            lines: 2,
+            valid: 2,
+            missing: Some(strings(&[
+                "aa ab", "ab bb", "ba aa", "ba ab", "bb ba", "bb bb"
+            ])),
+            ..verify::VerifyReport::default()
+        }
+    );
+}